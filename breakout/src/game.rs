@@ -1,6 +1,6 @@
 use anyhow::{anyhow, Error, Result};
 use geometry::coord2d::{BoundingBox, Point};
-use intcode::{CPUState, Computer, Program};
+use intcode::{CPUState, Computer, Patch, Program};
 
 use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
@@ -175,7 +175,8 @@ impl Breakout {
     }
 
     pub fn new_with_coins(mut program: Program, controller: BoxedController) -> Self {
-        program.insert(0, 2).unwrap();
+        let coins: Patch = include_str!("../coins.patch").parse().unwrap();
+        program.apply_patch(&coins).unwrap();
         Breakout::new(program, controller)
     }
 
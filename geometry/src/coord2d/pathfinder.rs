@@ -1,8 +1,9 @@
-//! Pathfinding in two dimensions using dijkstra's algorithm
+//! Pathfinding in two dimensions using bidirectional dijkstra's algorithm
 use std::clone::Clone;
-use std::cmp::{Eq, Ord, Ordering, PartialEq, PartialOrd};
 
-use searcher::{dijkstra, SearchCacher, SearchCandidate, SearchState};
+use searcher::{
+    bidirectional, SearchCacher, SearchCandidate, SearchOrd, SearchReversible, SearchState,
+};
 
 pub use super::map::Map;
 pub use super::path::Path;
@@ -10,8 +11,9 @@ use super::{Direction, Point};
 
 /// Holds information about a Path while the search
 /// algorithm (in searcher) runs.
-#[derive(Debug)]
+#[derive(Debug, SearchOrd)]
 struct PathCandidate<'m, M> {
+    #[search(cost = "distance")]
     path: Path,
     map: &'m M,
     target: &'m Point,
@@ -27,26 +29,6 @@ impl<'m, M> Clone for PathCandidate<'m, M> {
     }
 }
 
-impl<'m, M> Ord for PathCandidate<'m, M> {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.path.distance().cmp(&other.path.distance()).reverse()
-    }
-}
-
-impl<'m, M> PartialOrd for PathCandidate<'m, M> {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl<'m, M> PartialEq for PathCandidate<'m, M> {
-    fn eq(&self, other: &Self) -> bool {
-        self.path.distance().eq(&other.path.distance())
-    }
-}
-
-impl<'m, M> Eq for PathCandidate<'m, M> {}
-
 impl<'m, M> PathCandidate<'m, M>
 where
     M: Map,
@@ -113,6 +95,23 @@ where
     }
 }
 
+impl<'m, M> SearchReversible for PathCandidate<'m, M>
+where
+    M: Map,
+{
+    fn join(&self, backward: &Self) -> Self {
+        let path = self
+            .path
+            .follow(&backward.path.reversed())
+            .expect("Joined paths meet at the same point");
+        Self {
+            path,
+            map: self.map,
+            target: self.target,
+        }
+    }
+}
+
 /// Implements pathfinding for a map.
 #[derive(Debug, Clone)]
 pub struct Pathfinder<'m, M> {
@@ -132,12 +131,13 @@ where
     ///
     /// When no path exists and the search is exhausted, return None.
     pub fn find_path(&self, origin: Point, destination: Point) -> Option<Path> {
-        if !self.map.is_traversable(origin) {
+        if !self.map.is_traversable(origin) || !self.map.is_traversable(destination) {
             return None;
         }
-        let start = PathCandidate::start(origin, self.map, &destination);
+        let forward = PathCandidate::start(origin, self.map, &destination);
+        let backward = PathCandidate::start(destination, self.map, &origin);
 
-        dijkstra::run(start).ok().map(|c| c.path)
+        bidirectional::run(forward, backward).ok().map(|c| c.path)
     }
 }
 
@@ -158,6 +158,15 @@ mod test {
         );
     }
 
+    #[test]
+    fn wall() {
+        let map: SimpleMap =
+            vec![(0, 0).into(), (1, 0).into(), (2, 0).into(), (3, 0).into()].into();
+
+        assert_eq!(map.path((0, 0).into(), (4, 0).into()), None);
+        assert_eq!(map.path((4, 0).into(), (0, 0).into()), None);
+    }
+
     #[test]
     fn shortest() {
         let map: SimpleMap = include_str!("../../examples/pathfinding_multi.txt")
@@ -0,0 +1,105 @@
+#ifndef INTCODE_H
+#define INTCODE_H
+
+/* Generated with cbindgen from src/lib.rs, do not edit by hand. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+// What a machine did when it was last stepped.
+typedef enum IntcodeState {
+  // An instruction ran, and the machine can keep going.
+  INTCODE_STATE_CONTINUE = 0,
+  // A value was output, and can be read with `intcode_output`.
+  INTCODE_STATE_OUTPUT = 1,
+  // The machine is waiting for a value from `intcode_feed`.
+  INTCODE_STATE_INPUT = 2,
+  // The program has finished.
+  INTCODE_STATE_HALT = 3,
+  // Something went wrong, described by `intcode_error`.
+  INTCODE_STATE_ERROR = -1,
+} IntcodeState;
+
+// An Intcode machine, owned by the caller. Create one with
+// `intcode_new` and release it with `intcode_free`.
+typedef struct IntcodeMachine IntcodeMachine;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Create a machine running a copy of `length` integers from `program`.
+//
+// Returns NULL if `program` is NULL.
+//
+// # Safety
+//
+// `program` must point to at least `length` readable integers.
+struct IntcodeMachine *intcode_new(const int64_t *program, size_t length);
+
+// Release a machine. Passing NULL does nothing.
+//
+// # Safety
+//
+// `machine` must have come from `intcode_new`, and must not be used again.
+void intcode_free(struct IntcodeMachine *machine);
+
+// Run a single instruction.
+//
+// # Safety
+//
+// `machine` must be a live pointer from `intcode_new`.
+enum IntcodeState intcode_step(struct IntcodeMachine *machine);
+
+// Run until the machine outputs a value, needs input, halts or fails.
+//
+// # Safety
+//
+// `machine` must be a live pointer from `intcode_new`.
+enum IntcodeState intcode_run(struct IntcodeMachine *machine);
+
+// Provide the next input value. Returns 0 on success, or -1 if the
+// machine already has an input waiting to be used.
+//
+// # Safety
+//
+// `machine` must be a live pointer from `intcode_new`.
+int intcode_feed(struct IntcodeMachine *machine, int64_t value);
+
+// The most recent value output by the machine.
+//
+// # Safety
+//
+// `machine` must be a live pointer from `intcode_new`.
+int64_t intcode_output(const struct IntcodeMachine *machine);
+
+// The address of the next instruction to run.
+//
+// # Safety
+//
+// `machine` must be a live pointer from `intcode_new`.
+int64_t intcode_pc(const struct IntcodeMachine *machine);
+
+// Read the value at an address into `value`. Returns 0 on success,
+// or -1 if the address is negative.
+//
+// # Safety
+//
+// `machine` must be a live pointer from `intcode_new`, and `value`
+// must point to a writable integer.
+int intcode_read(const struct IntcodeMachine *machine, int64_t address, int64_t *value);
+
+// A description of the last error, or NULL if nothing has gone wrong.
+// The string is owned by the machine, and is valid until the next call
+// which changes it or until the machine is freed.
+//
+// # Safety
+//
+// `machine` must be a live pointer from `intcode_new`.
+const char *intcode_error(const struct IntcodeMachine *machine);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* INTCODE_H */
@@ -0,0 +1,291 @@
+//! A C interface to the Intcode VM, built as a `cdylib`.
+//!
+//! The header in `include/intcode.h` is generated from this crate with
+//! `cbindgen --config cbindgen.toml --output include/intcode.h`.
+
+use std::any::Any;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use intcode::{CPUState, Computer, IntMem, IntcodeError};
+
+/// An Intcode machine, owned by the caller. Create one with
+/// `intcode_new` and release it with `intcode_free`.
+pub struct IntcodeMachine {
+    cpu: Computer,
+    output: IntMem,
+    error: Option<CString>,
+}
+
+impl IntcodeMachine {
+    fn fail(&mut self, error: IntcodeError) -> IntcodeState {
+        let message = match &error {
+            IntcodeError::Runtime(e) => e.report(),
+            e => e.to_string(),
+        };
+        self.error = CString::new(message).ok();
+        IntcodeState::Error
+    }
+
+    fn step(&mut self) -> IntcodeState {
+        let cpu = &mut self.cpu;
+        match panic::catch_unwind(AssertUnwindSafe(|| cpu.op())) {
+            Ok(Ok(CPUState::Continue)) => IntcodeState::Continue,
+            Ok(Ok(CPUState::Output(value))) => {
+                self.output = value;
+                IntcodeState::Output
+            }
+            Ok(Ok(CPUState::Input)) => IntcodeState::Input,
+            Ok(Ok(CPUState::Halt)) => IntcodeState::Halt,
+            Ok(Err(e)) => self.fail(e),
+            Err(payload) => {
+                let message = format!("Intcode machine panicked: {}", panic_message(&*payload));
+                self.error = CString::new(message).ok();
+                IntcodeState::Error
+            }
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(|s| s.as_str()))
+        .unwrap_or("unknown cause")
+}
+
+// Panics must not unwind into C, so every entry point returns
+// `fallback` instead.
+fn guard<T, F>(fallback: T, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+/// What a machine did when it was last stepped.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum IntcodeState {
+    /// An instruction ran, and the machine can keep going.
+    Continue = 0,
+    /// A value was output, and can be read with `intcode_output`.
+    Output = 1,
+    /// The machine is waiting for a value from `intcode_feed`.
+    Input = 2,
+    /// The program has finished.
+    Halt = 3,
+    /// Something went wrong, described by `intcode_error`.
+    Error = -1,
+}
+
+/// Create a machine running a copy of `length` integers from `program`.
+///
+/// Returns NULL if `program` is NULL.
+///
+/// # Safety
+///
+/// `program` must point to at least `length` readable integers.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_new(program: *const i64, length: usize) -> *mut IntcodeMachine {
+    if program.is_null() {
+        return ptr::null_mut();
+    }
+    let tape = slice::from_raw_parts(program, length);
+
+    guard(ptr::null_mut(), || {
+        Box::into_raw(Box::new(IntcodeMachine {
+            cpu: Computer::new(tape.to_vec()),
+            output: 0,
+            error: None,
+        }))
+    })
+}
+
+/// Release a machine. Passing NULL does nothing.
+///
+/// # Safety
+///
+/// `machine` must have come from `intcode_new`, and must not be used again.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_free(machine: *mut IntcodeMachine) {
+    if !machine.is_null() {
+        let machine = Box::from_raw(machine);
+        guard((), move || drop(machine));
+    }
+}
+
+/// Run a single instruction.
+///
+/// # Safety
+///
+/// `machine` must be a live pointer from `intcode_new`.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_step(machine: *mut IntcodeMachine) -> IntcodeState {
+    match machine.as_mut() {
+        Some(m) => guard(IntcodeState::Error, || m.step()),
+        None => IntcodeState::Error,
+    }
+}
+
+/// Run until the machine outputs a value, needs input, halts or fails.
+///
+/// # Safety
+///
+/// `machine` must be a live pointer from `intcode_new`.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_run(machine: *mut IntcodeMachine) -> IntcodeState {
+    let m = match machine.as_mut() {
+        Some(m) => m,
+        None => return IntcodeState::Error,
+    };
+
+    guard(IntcodeState::Error, || loop {
+        let state = m.step();
+        if state != IntcodeState::Continue {
+            return state;
+        }
+    })
+}
+
+/// Provide the next input value. Returns 0 on success, or -1 if the
+/// machine already has an input waiting to be used.
+///
+/// # Safety
+///
+/// `machine` must be a live pointer from `intcode_new`.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_feed(machine: *mut IntcodeMachine, value: i64) -> c_int {
+    let m = match machine.as_mut() {
+        Some(m) => m,
+        None => return -1,
+    };
+
+    guard(-1, || match m.cpu.feed(value) {
+        Ok(()) => 0,
+        Err(e) => {
+            m.fail(e);
+            -1
+        }
+    })
+}
+
+/// The most recent value output by the machine.
+///
+/// # Safety
+///
+/// `machine` must be a live pointer from `intcode_new`.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_output(machine: *const IntcodeMachine) -> i64 {
+    guard(0, || machine.as_ref().map(|m| m.output).unwrap_or(0))
+}
+
+/// The address of the next instruction to run.
+///
+/// # Safety
+///
+/// `machine` must be a live pointer from `intcode_new`.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_pc(machine: *const IntcodeMachine) -> i64 {
+    guard(0, || machine.as_ref().map(|m| m.cpu.pc()).unwrap_or(0))
+}
+
+/// Read the value at an address into `value`. Returns 0 on success,
+/// or -1 if the address is negative.
+///
+/// # Safety
+///
+/// `machine` must be a live pointer from `intcode_new`, and `value`
+/// must point to a writable integer.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_read(
+    machine: *const IntcodeMachine,
+    address: i64,
+    value: *mut i64,
+) -> c_int {
+    guard(-1, || match (machine.as_ref(), value.as_mut()) {
+        (Some(m), Some(v)) if address >= 0 => {
+            *v = m.cpu.peek(address).unwrap_or(0);
+            0
+        }
+        _ => -1,
+    })
+}
+
+/// A description of the last error, or NULL if nothing has gone wrong.
+/// The string is owned by the machine, and is valid until the next call
+/// which changes it or until the machine is freed.
+///
+/// # Safety
+///
+/// `machine` must be a live pointer from `intcode_new`.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_error(machine: *const IntcodeMachine) -> *const c_char {
+    guard(ptr::null(), || {
+        machine
+            .as_ref()
+            .and_then(|m| m.error.as_ref())
+            .map(|e| e.as_ptr())
+            .unwrap_or(ptr::null())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn echo() {
+        let program = [3, 0, 4, 0, 99];
+        unsafe {
+            let m = intcode_new(program.as_ptr(), program.len());
+            assert_eq!(intcode_run(m), IntcodeState::Input);
+            assert_eq!(intcode_feed(m, 42), 0);
+            assert_eq!(intcode_run(m), IntcodeState::Output);
+            assert_eq!(intcode_output(m), 42);
+            assert_eq!(intcode_pc(m), 4);
+
+            let mut value = 0;
+            assert_eq!(intcode_read(m, 0, &mut value), 0);
+            assert_eq!(value, 42);
+
+            assert_eq!(intcode_run(m), IntcodeState::Halt);
+            assert!(intcode_error(m).is_null());
+            intcode_free(m);
+        }
+    }
+
+    #[test]
+    fn error() {
+        let program = [42];
+        unsafe {
+            let m = intcode_new(program.as_ptr(), program.len());
+            assert_eq!(intcode_step(m), IntcodeState::Error);
+
+            let message = CStr::from_ptr(intcode_error(m)).to_str().unwrap();
+            assert!(message.starts_with("Unknown opcode: 42"));
+            intcode_free(m);
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn panic() {
+        // Adding one to the largest integer overflows, which panics
+        // in debug builds.
+        let program = [1101, i64::MAX, 1, 0, 99];
+        unsafe {
+            let m = intcode_new(program.as_ptr(), program.len());
+            assert_eq!(intcode_run(m), IntcodeState::Error);
+
+            let message = CStr::from_ptr(intcode_error(m)).to_str().unwrap();
+            assert!(message.starts_with("Intcode machine panicked"));
+            intcode_free(m);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Run a Computer on its own thread, with input and output over channels.
+
+use std::sync::mpsc::{self, Receiver, SendError, Sender};
+use std::thread::{self, JoinHandle};
+
+use crate::cpu::{CPUState, Computer};
+use crate::errors::{IntcodeError, Result};
+use crate::program::Program;
+use crate::IntMem;
+
+/// Run a computer on a new thread, reading input from one channel
+/// and writing output to another.
+///
+/// The thread finishes when the program halts, returning the final
+/// state of memory. If the input channel is closed while the program
+/// is waiting for input, the thread finishes with [IntcodeError::NoInput].
+/// Output sent after the receiver has gone away is discarded.
+pub fn spawn_connected(
+    mut cpu: Computer,
+    input: Receiver<IntMem>,
+    output: Sender<IntMem>,
+) -> JoinHandle<Result<Program>> {
+    thread::spawn(move || {
+        loop {
+            match cpu.op()? {
+                CPUState::Continue => {}
+                CPUState::Output(v) => {
+                    let _ = output.send(v);
+                }
+                CPUState::Input => {
+                    let value = input.recv().map_err(|_| IntcodeError::NoInput)?;
+                    cpu.feed(value)?;
+                }
+                CPUState::Halt => break,
+            }
+        }
+        Ok(cpu.program())
+    })
+}
+
+/// A computer running on its own thread.
+#[derive(Debug)]
+pub struct Actor {
+    input: Sender<IntMem>,
+    output: Receiver<IntMem>,
+    handle: JoinHandle<Result<Program>>,
+}
+
+impl Actor {
+    pub fn spawn(cpu: Computer) -> Self {
+        let (input, irx) = mpsc::channel();
+        let (otx, output) = mpsc::channel();
+
+        Actor {
+            input,
+            output,
+            handle: spawn_connected(cpu, irx, otx),
+        }
+    }
+
+    /// Send a value to the program's input.
+    pub fn send(&self, value: IntMem) -> std::result::Result<(), SendError<IntMem>> {
+        self.input.send(value)
+    }
+
+    /// Wait for the next value output by the program. Returns None
+    /// once the program has stopped.
+    pub fn recv(&self) -> Option<IntMem> {
+        self.output.recv().ok()
+    }
+
+    /// A handle to the input channel, which can be given to other threads.
+    pub fn sender(&self) -> Sender<IntMem> {
+        self.input.clone()
+    }
+
+    pub fn receiver(&self) -> &Receiver<IntMem> {
+        &self.output
+    }
+
+    /// Wait for the program to stop, returning its final memory.
+    pub fn join(self) -> Result<Program> {
+        drop(self.input);
+        self.handle
+            .join()
+            .unwrap_or_else(|e| std::panic::resume_unwind(e))
+    }
+}
+
+impl Iterator for Actor {
+    type Item = IntMem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn echo() {
+        let actor = Actor::spawn(Computer::new(vec![3, 0, 4, 0, 99]));
+        actor.send(5).unwrap();
+        assert_eq!(actor.recv(), Some(5));
+        assert_eq!(actor.recv(), None);
+
+        let program = actor.join().unwrap();
+        assert_eq!(program.get(0), Some(5));
+    }
+
+    #[test]
+    fn closed_input() {
+        let actor = Actor::spawn(Computer::new(vec![3, 0, 4, 0, 99]));
+        assert!(matches!(actor.join(), Err(IntcodeError::NoInput)));
+    }
+
+    #[test]
+    fn feedback_loop_day7() {
+        let program: Vec<IntMem> = vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+        let phases = [9, 8, 7, 6, 5];
+
+        // Amplifiers are chained together, with the final amplifier
+        // reporting back here so that we can see each signal before
+        // passing it back to the first amplifier.
+        let (senders, mut receivers): (Vec<_>, VecDeque<_>) =
+            (0..=phases.len()).map(|_| mpsc::channel()).unzip();
+        for (tx, phase) in senders.iter().zip(phases.iter()) {
+            tx.send(*phase).unwrap();
+        }
+
+        let handles: Vec<_> = senders
+            .iter()
+            .skip(1)
+            .map(|tx| {
+                let rx = receivers.pop_front().unwrap();
+                spawn_connected(Computer::new(program.clone()), rx, tx.clone())
+            })
+            .collect();
+        let output = receivers.pop_front().unwrap();
+        let first = senders[0].clone();
+        drop(senders);
+
+        first.send(0).unwrap();
+        let mut signal = None;
+        for value in output.iter() {
+            signal = Some(value);
+            let _ = first.send(value);
+        }
+
+        assert_eq!(signal, Some(139629729));
+        for handle in handles {
+            handle.join().unwrap().unwrap();
+        }
+    }
+}
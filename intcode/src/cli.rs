@@ -1,6 +1,6 @@
 use anyhow::{anyhow, Error};
 use clap::{App, Arg};
-use intcode::{CPUState, Computer, IntMem, Program};
+use intcode::{CPUState, Computer, IntMem, IntcodeError, Patch, Program};
 use std::collections::VecDeque;
 use std::fs::File;
 
@@ -19,6 +19,11 @@ fn program(filename: Option<&str>) -> Result<Program> {
     Ok(Program::read(reader)?)
 }
 
+fn patch(filename: &str) -> Result<Patch> {
+    let f: File = File::open(filename)?;
+    Patch::read(Box::new(f))
+}
+
 fn main() -> Result<()> {
     let matches = App::new("Intcode Interpreter - Advent of Code 2019")
         .version("1.0")
@@ -33,6 +38,17 @@ fn main() -> Result<()> {
                 .multiple(true)
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("patch")
+                .short("p")
+                .long("patch")
+                .value_name("PATCH")
+                .help("Apply a patch file to the program before running it")
+                .required(false)
+                .multiple(true)
+                .number_of_values(1)
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("program")
                 .value_name("PROGRAM")
@@ -43,7 +59,13 @@ fn main() -> Result<()> {
         .get_matches();
 
     let filename = matches.value_of("program");
-    let prog = program(filename)?;
+    let mut prog = program(filename)?;
+
+    if let Some(patches) = matches.values_of("patch") {
+        for filename in patches {
+            prog.apply_patch(&patch(filename)?)?;
+        }
+    }
 
     let mut cpu = Computer::new(prog);
 
@@ -56,7 +78,16 @@ fn main() -> Result<()> {
         .transpose()?;
 
     loop {
-        match cpu.op()? {
+        let state = match cpu.op() {
+            Ok(state) => state,
+            Err(IntcodeError::Runtime(e)) => {
+                eprint!("{}", e.report());
+                std::process::exit(1);
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        match state {
             CPUState::Continue => {}
             CPUState::Output(v) => println!("{}", v),
             CPUState::Halt => break,
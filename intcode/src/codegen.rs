@@ -0,0 +1,647 @@
+//! Translate Intcode programs into standalone C or Rust source.
+//!
+//! Each reachable instruction becomes a block of native code, selected
+//! by a switch on the program counter. Blocks check that their opcode
+//! is still in memory before they run, and fall back to a small
+//! interpreter when it isn't, so self-modifying programs still work.
+//! Operands are always read from memory, for the same reason.
+//!
+//! The generated program reads whitespace separated input values from
+//! stdin, and writes each output value to stdout on its own line.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Write;
+use core::str::FromStr;
+
+use crate::opcode::{Op, ParameterMode};
+use crate::optimizer::{Analysis, Decoded};
+use crate::program::{Arguments, Program};
+use crate::IntMem;
+
+/// The language to generate source code in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Language {
+    C,
+    Rust,
+}
+
+#[derive(Debug)]
+pub struct ParseLanguageError(String);
+
+impl fmt::Display for ParseLanguageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown language {}, expected c or rust", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseLanguageError {}
+
+impl FromStr for Language {
+    type Err = ParseLanguageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "c" => Ok(Language::C),
+            "rust" | "rs" => Ok(Language::Rust),
+            _ => Err(ParseLanguageError(s.to_string())),
+        }
+    }
+}
+
+/// Generate a complete source file which runs this program.
+pub fn transpile(program: &Program, language: Language) -> String {
+    let tape = program.tape();
+    let analysis = Analysis::new(&tape, None);
+    let blocks: Vec<&Decoded> = analysis
+        .instructions
+        .values()
+        .filter(|d| compilable(d))
+        .collect();
+
+    match language {
+        Language::C => c_source(&tape, &blocks),
+        Language::Rust => rust_source(&tape, &blocks),
+    }
+}
+
+// Instructions which write in immediate mode are left to the
+// interpreter, which reports the error.
+fn compilable(d: &Decoded) -> bool {
+    let target = match d.op() {
+        Op::Add | Op::Mul | Op::LessThan | Op::EqualTo => Some(3),
+        Op::Input => Some(1),
+        _ => None,
+    };
+    target
+        .map(|p| d.mode(p) != ParameterMode::Immediate)
+        .unwrap_or(true)
+}
+
+fn words(tape: &[IntMem]) -> String {
+    tape.chunks(16)
+        .map(|chunk| {
+            let line: Vec<String> = chunk.iter().map(|w| w.to_string()).collect();
+            format!("    {},", line.join(", "))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Expressions for reading an operand and for the address written to,
+/// in one of the target languages.
+trait Syntax {
+    fn cell(&self, address: IntMem) -> String;
+    fn load(&self, address: &str) -> String;
+    fn relative(&self, offset: &str) -> String;
+
+    fn operand(&self, d: &Decoded, parameter: u32) -> String {
+        let cell = self.cell(d.pc + parameter as IntMem);
+        match d.mode(parameter) {
+            ParameterMode::Immediate => cell,
+            ParameterMode::Position => self.load(&cell),
+            ParameterMode::Relative => self.load(&self.relative(&cell)),
+        }
+    }
+
+    fn target(&self, d: &Decoded, parameter: u32) -> String {
+        let cell = self.cell(d.pc + parameter as IntMem);
+        match d.mode(parameter) {
+            ParameterMode::Relative => self.relative(&cell),
+            _ => cell,
+        }
+    }
+}
+
+struct C;
+
+impl Syntax for C {
+    fn cell(&self, address: IntMem) -> String {
+        format!("mem[{}]", address)
+    }
+
+    fn load(&self, address: &str) -> String {
+        format!("load({})", address)
+    }
+
+    fn relative(&self, offset: &str) -> String {
+        format!("rb + {}", offset)
+    }
+}
+
+fn c_block(d: &Decoded) -> String {
+    let s = C;
+    let next = d.pc + d.len();
+    let a = || s.operand(d, 1);
+    let b = || s.operand(d, 2);
+
+    let body = match d.op() {
+        Op::Add => format!("store({}, {} + {});", s.target(d, 3), a(), b()),
+        Op::Mul => format!("store({}, {} * {});", s.target(d, 3), a(), b()),
+        Op::LessThan => format!("store({}, {} < {});", s.target(d, 3), a(), b()),
+        Op::EqualTo => format!("store({}, {} == {});", s.target(d, 3), a(), b()),
+        Op::Input => format!("store({}, input());", s.target(d, 1)),
+        Op::Output => format!("output({});", a()),
+        Op::MoveStack => format!("rb += {};", a()),
+        Op::JumpIfTrue => format!(
+            "if ({} != 0) {{\n                pc = {};\n                continue;\n            }}",
+            a(),
+            b()
+        ),
+        Op::JumpIfFalse => format!(
+            "if ({} == 0) {{\n                pc = {};\n                continue;\n            }}",
+            a(),
+            b()
+        ),
+        Op::Halt => "return 0;".to_string(),
+    };
+    let advance = match d.op() {
+        Op::Halt => String::new(),
+        _ => format!("\n            pc = {};\n            continue;", next),
+    };
+
+    format!(
+        "        case {pc}: /* {op} */\n            if (mem[{pc}] != {word}) break;\n            {body}{advance}\n",
+        pc = d.pc,
+        op = d.op(),
+        word = d.word(),
+        body = body,
+        advance = advance
+    )
+}
+
+fn c_source(tape: &[IntMem], blocks: &[&Decoded]) -> String {
+    let mut source = String::new();
+    source.push_str(C_PRELUDE);
+    writeln!(
+        source,
+        "static const word program[{}] = {{\n{}\n}};\n",
+        tape.len().max(1),
+        words(tape)
+    )
+    .unwrap();
+    source.push_str(C_INTERPRETER);
+
+    source.push_str("int main(void) {\n    word pc = 0, rb = 0;\n\n");
+    source.push_str("    size = sizeof(program) / sizeof(word);\n");
+    source.push_str("    mem = calloc(size, sizeof(word));\n");
+    source.push_str("    if (!mem) fail(\"Out of memory\", 0);\n");
+    source.push_str("    memcpy(mem, program, sizeof(program));\n\n");
+    source.push_str("    for (;;) {\n        switch (pc) {\n");
+    for d in blocks {
+        source.push_str(&c_block(d));
+    }
+    source.push_str("        }\n\n");
+    source.push_str("        /* Code which was not compiled, or has been rewritten. */\n");
+    source.push_str("        if (!step(&pc, &rb)) return 0;\n    }\n}\n");
+    source
+}
+
+const C_PRELUDE: &str = r#"/* Generated by intcode-transpile. */
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef int64_t word;
+
+static word *mem;
+static size_t size;
+
+static _Noreturn void fail(const char *message, word value) {
+    fflush(stdout);
+    fprintf(stderr, "%s: %lld\n", message, (long long)value);
+    exit(1);
+}
+
+static word load(word address) {
+    if (address < 0) fail("Invalid address", address);
+    return (size_t)address < size ? mem[address] : 0;
+}
+
+static void store(word address, word value) {
+    if (address < 0) fail("Invalid address", address);
+    if ((size_t)address >= size) {
+        size_t grown = size * 2 > (size_t)address ? size * 2 : (size_t)address + 1;
+        mem = realloc(mem, grown * sizeof(word));
+        if (!mem) fail("Out of memory", address);
+        memset(mem + size, 0, (grown - size) * sizeof(word));
+        size = grown;
+    }
+    mem[address] = value;
+}
+
+static word input(void) {
+    long long value;
+    fflush(stdout);
+    if (scanf("%lld", &value) != 1) fail("No input available", 0);
+    return value;
+}
+
+static void output(word value) {
+    printf("%lld\n", (long long)value);
+}
+
+"#;
+
+const C_INTERPRETER: &str = r#"static word mode(word pc, int parameter) {
+    word divisor = 10;
+    for (int i = 0; i < parameter; i++) divisor *= 10;
+    return load(pc) / divisor % 10;
+}
+
+static word address(word pc, word rb, int parameter) {
+    switch (mode(pc, parameter)) {
+    case 0: return load(pc + parameter);
+    case 2: return rb + load(pc + parameter);
+    case 1: fail("Illegal parameter mode", pc);
+    default: fail("Invalid parameter mode", pc);
+    }
+}
+
+static word argument(word pc, word rb, int parameter) {
+    if (mode(pc, parameter) == 1) return load(pc + parameter);
+    return load(address(pc, rb, parameter));
+}
+
+/* Run a single instruction, returning 0 once the program halts. */
+static int step(word *pc, word *rb) {
+    word p = *pc, r = *rb;
+    switch (load(p) % 100) {
+    case 1: store(address(p, r, 3), argument(p, r, 1) + argument(p, r, 2)); *pc += 4; break;
+    case 2: store(address(p, r, 3), argument(p, r, 1) * argument(p, r, 2)); *pc += 4; break;
+    case 3: store(address(p, r, 1), input()); *pc += 2; break;
+    case 4: output(argument(p, r, 1)); *pc += 2; break;
+    case 5: *pc = argument(p, r, 1) != 0 ? argument(p, r, 2) : p + 3; break;
+    case 6: *pc = argument(p, r, 1) == 0 ? argument(p, r, 2) : p + 3; break;
+    case 7: store(address(p, r, 3), argument(p, r, 1) < argument(p, r, 2)); *pc += 4; break;
+    case 8: store(address(p, r, 3), argument(p, r, 1) == argument(p, r, 2)); *pc += 4; break;
+    case 9: *rb += argument(p, r, 1); *pc += 2; break;
+    case 99: return 0;
+    default: fail("Unknown opcode", load(p));
+    }
+    return 1;
+}
+
+"#;
+
+struct Rust;
+
+impl Syntax for Rust {
+    fn cell(&self, address: IntMem) -> String {
+        format!("m.mem[{}]", address)
+    }
+
+    fn load(&self, address: &str) -> String {
+        format!("m.load({})", address)
+    }
+
+    fn relative(&self, offset: &str) -> String {
+        format!("m.rb + {}", offset)
+    }
+}
+
+fn rust_block(d: &Decoded) -> String {
+    let s = Rust;
+    let next = d.pc + d.len();
+    let a = || s.operand(d, 1);
+    let b = || s.operand(d, 2);
+
+    let body = match d.op() {
+        Op::Add => format!("m.store({}, {} + {});", s.target(d, 3), a(), b()),
+        Op::Mul => format!("m.store({}, {} * {});", s.target(d, 3), a(), b()),
+        Op::LessThan => format!(
+            "m.store({}, ({} < {}) as i64);",
+            s.target(d, 3),
+            a(),
+            b()
+        ),
+        Op::EqualTo => format!(
+            "m.store({}, ({} == {}) as i64);",
+            s.target(d, 3),
+            a(),
+            b()
+        ),
+        Op::Input => format!(
+            "let value = m.input();\n                m.store({}, value);",
+            s.target(d, 1)
+        ),
+        Op::Output => format!("output({});", a()),
+        Op::MoveStack => format!("m.rb += {};", a()),
+        Op::JumpIfTrue => format!(
+            "if {} != 0 {{\n                    m.pc = {};\n                    continue;\n                }}",
+            a(),
+            b()
+        ),
+        Op::JumpIfFalse => format!(
+            "if {} == 0 {{\n                    m.pc = {};\n                    continue;\n                }}",
+            a(),
+            b()
+        ),
+        Op::Halt => {
+            return format!(
+                "            // {op}\n            {pc} if m.mem[{pc}] == {word} => break,\n",
+                op = d.op(),
+                pc = d.pc,
+                word = d.word()
+            )
+        }
+    };
+
+    format!(
+        "            // {op}\n            {pc} if m.mem[{pc}] == {word} => {{\n                {body}\n                m.pc = {next};\n            }}\n",
+        pc = d.pc,
+        op = d.op(),
+        word = d.word(),
+        body = body,
+        next = next
+    )
+}
+
+fn rust_source(tape: &[IntMem], blocks: &[&Decoded]) -> String {
+    let mut source = String::new();
+    source.push_str(RUST_PRELUDE);
+    writeln!(
+        source,
+        "const PROGRAM: [i64; {}] = [\n{}\n];\n",
+        tape.len(),
+        words(tape)
+    )
+    .unwrap();
+    source.push_str(RUST_INTERPRETER);
+
+    source.push_str(
+        "fn main() {\n    let mut m = Machine::new();\n\n    loop {\n        match m.pc {\n",
+    );
+    for d in blocks {
+        source.push_str(&rust_block(d));
+    }
+    source.push_str("            // Code which was not compiled, or has been rewritten.\n");
+    source.push_str("            _ => {\n                if !m.step() {\n                    break;\n                }\n            }\n");
+    source.push_str("        }\n    }\n}\n");
+    source
+}
+
+const RUST_PRELUDE: &str = r#"// Generated by intcode-transpile.
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::process;
+
+"#;
+
+const RUST_INTERPRETER: &str = r#"fn fail(message: &str, value: i64) -> ! {
+    let _ = io::stdout().flush();
+    eprintln!("{}: {}", message, value);
+    process::exit(1)
+}
+
+fn output(value: i64) {
+    println!("{}", value);
+}
+
+struct Machine {
+    mem: Vec<i64>,
+    pc: i64,
+    rb: i64,
+    pending: VecDeque<i64>,
+}
+
+impl Machine {
+    fn new() -> Self {
+        Machine {
+            mem: PROGRAM.to_vec(),
+            pc: 0,
+            rb: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    fn load(&self, address: i64) -> i64 {
+        if address < 0 {
+            fail("Invalid address", address);
+        }
+        self.mem.get(address as usize).copied().unwrap_or(0)
+    }
+
+    fn store(&mut self, address: i64, value: i64) {
+        if address < 0 {
+            fail("Invalid address", address);
+        }
+        let address = address as usize;
+        if address >= self.mem.len() {
+            let grown = (self.mem.len() * 2).max(address + 1);
+            self.mem.resize(grown, 0);
+        }
+        self.mem[address] = value;
+    }
+
+    fn input(&mut self) -> i64 {
+        while self.pending.is_empty() {
+            let _ = io::stdout().flush();
+            let mut line = String::new();
+            if io::stdin().read_line(&mut line).unwrap_or(0) == 0 {
+                fail("No input available", self.pc);
+            }
+            for token in line.split_whitespace() {
+                match token.parse() {
+                    Ok(value) => self.pending.push_back(value),
+                    Err(_) => fail("Invalid input at", self.pc),
+                }
+            }
+        }
+        self.pending.pop_front().unwrap()
+    }
+
+    fn mode(&self, parameter: u32) -> i64 {
+        self.load(self.pc) / (10 * 10i64.pow(parameter)) % 10
+    }
+
+    fn address(&self, parameter: u32) -> i64 {
+        let value = self.load(self.pc + parameter as i64);
+        match self.mode(parameter) {
+            0 => value,
+            2 => self.rb + value,
+            1 => fail("Illegal parameter mode", self.pc),
+            _ => fail("Invalid parameter mode", self.pc),
+        }
+    }
+
+    fn argument(&self, parameter: u32) -> i64 {
+        if self.mode(parameter) == 1 {
+            return self.load(self.pc + parameter as i64);
+        }
+        self.load(self.address(parameter))
+    }
+
+    /// Run a single instruction, returning false once the program halts.
+    fn step(&mut self) -> bool {
+        let pc = self.pc;
+        match self.load(pc) % 100 {
+            1 => {
+                let value = self.argument(1) + self.argument(2);
+                self.store(self.address(3), value);
+                self.pc += 4;
+            }
+            2 => {
+                let value = self.argument(1) * self.argument(2);
+                self.store(self.address(3), value);
+                self.pc += 4;
+            }
+            3 => {
+                let value = self.input();
+                self.store(self.address(1), value);
+                self.pc += 2;
+            }
+            4 => {
+                output(self.argument(1));
+                self.pc += 2;
+            }
+            5 => {
+                self.pc = if self.argument(1) != 0 { self.argument(2) } else { pc + 3 };
+            }
+            6 => {
+                self.pc = if self.argument(1) == 0 { self.argument(2) } else { pc + 3 };
+            }
+            7 => {
+                let value = (self.argument(1) < self.argument(2)) as i64;
+                self.store(self.address(3), value);
+                self.pc += 4;
+            }
+            8 => {
+                let value = (self.argument(1) == self.argument(2)) as i64;
+                self.store(self.address(3), value);
+                self.pc += 4;
+            }
+            9 => {
+                self.rb += self.argument(1);
+                self.pc += 2;
+            }
+            99 => return false,
+            _ => fail("Unknown opcode", self.load(pc)),
+        }
+        true
+    }
+}
+
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_language() {
+        assert_eq!("c".parse::<Language>().unwrap(), Language::C);
+        assert_eq!("Rust".parse::<Language>().unwrap(), Language::Rust);
+        assert!("python".parse::<Language>().is_err());
+    }
+
+    #[test]
+    fn c_blocks() {
+        let program: Program = vec![3, 9, 1001, 9, 5, 9, 4, 9, 99, 0].into();
+        let source = transpile(&program, Language::C);
+
+        assert!(source.contains("static const word program[10]"));
+        assert!(source.contains("case 2: /* Add */"));
+        assert!(source.contains("if (mem[2] != 1001) break;"));
+        assert!(source.contains("store(mem[5], load(mem[3]) + mem[4]);"));
+        assert!(source.contains("output(load(mem[7]));"));
+
+        // The value at address 9 is data, never code.
+        assert!(!source.contains("case 9: /*"));
+    }
+
+    #[test]
+    fn rust_blocks() {
+        let program: Program = vec![109, 5, 1206, 0, 7, 99, 0, 99].into();
+        let source = transpile(&program, Language::Rust);
+
+        assert!(source.contains("const PROGRAM: [i64; 8]"));
+        assert!(source.contains("0 if m.mem[0] == 109 => {"));
+        assert!(source.contains("if m.load(m.rb + m.mem[3]) == 0 {"));
+        assert!(source.contains("7 if m.mem[7] == 99 => break,"));
+    }
+
+    // Build the generated Rust, and run it once for each input.
+    fn run_rust(name: &str, program: &Program, inputs: &[IntMem]) -> Vec<Vec<IntMem>> {
+        use std::io::Write as _;
+        use std::process::{Command, Stdio};
+
+        let dir = std::env::temp_dir().join(format!("intcode-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("main.rs");
+        let binary = dir.join("main");
+        std::fs::write(&source, transpile(program, Language::Rust)).unwrap();
+
+        let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+        let status = Command::new(rustc)
+            .args(["-O", "--edition", "2018", "-o"])
+            .arg(&binary)
+            .arg(&source)
+            .status()
+            .unwrap();
+        assert!(
+            status.success(),
+            "Generated source for {} doesn't compile",
+            name
+        );
+
+        let outputs = inputs
+            .iter()
+            .map(|input| {
+                let mut child = Command::new(&binary)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .spawn()
+                    .unwrap();
+                writeln!(child.stdin.take().unwrap(), "{}", input).unwrap();
+                let output = child.wait_with_output().unwrap();
+                assert!(output.status.success());
+                String::from_utf8(output.stdout)
+                    .unwrap()
+                    .lines()
+                    .map(|line| line.parse().unwrap())
+                    .collect()
+            })
+            .collect();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        outputs
+    }
+
+    fn run_computer(program: &Program, input: IntMem) -> Vec<IntMem> {
+        let mut cpu = crate::Computer::new(program.clone());
+        cpu.feed(input).unwrap();
+        cpu.follow().collect()
+    }
+
+    #[test]
+    fn rust_matches_computer() {
+        let puzzles = [
+            (
+                "day5",
+                include_str!("../../puzzles/5/input.txt"),
+                vec![1, 5],
+            ),
+            ("day9", include_str!("../../puzzles/9/input.txt"), vec![1]),
+        ];
+
+        for (name, text, inputs) in puzzles.iter() {
+            let program: Program = text.parse().unwrap();
+            let outputs = run_rust(name, &program, inputs);
+            for (input, output) in inputs.iter().zip(outputs) {
+                assert_eq!(
+                    output,
+                    run_computer(&program, *input),
+                    "{} ({})",
+                    name,
+                    input
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,381 @@
+//! Compare two programs, or two memory tapes, instruction by instruction.
+//!
+//! Each tape is split into instructions (found by following control flow
+//! from address 0) and data cells. The two sequences are aligned so that
+//! instructions with the same operation line up even when code has moved,
+//! and then each aligned pair is checked for changed modes, operands or
+//! data values.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::opcode::{OpCode, ParameterMode};
+use crate::optimizer::Analysis;
+use crate::program::Arguments;
+use crate::IntMem;
+
+/// A run of memory which is either one instruction or one data cell.
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Unit {
+    address: IntMem,
+    words: Vec<IntMem>,
+    instruction: bool,
+}
+
+impl Unit {
+    // Units which can be aligned with each other: data with data, and
+    // instructions with instructions of the same operation.
+    fn key(&self) -> Option<IntMem> {
+        if self.instruction {
+            Some(self.words[0] % 100)
+        } else {
+            None
+        }
+    }
+}
+
+fn units(tape: &[IntMem]) -> Vec<Unit> {
+    let analysis = Analysis::new(tape, None);
+    let mut units = Vec::new();
+    let mut address = 0;
+
+    while (address as usize) < tape.len() {
+        let unit = match analysis.instructions.get(&address) {
+            Some(d) => Unit {
+                address,
+                words: d.encode(),
+                instruction: true,
+            },
+            None => Unit {
+                address,
+                words: vec![tape[address as usize]],
+                instruction: false,
+            },
+        };
+        address += unit.words.len() as IntMem;
+        units.push(unit);
+    }
+    units
+}
+
+/// The ways an aligned part of a program can differ.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ChangeKind {
+    /// Only present in the right hand program.
+    Added,
+    /// Only present in the left hand program.
+    Removed,
+    /// The same operation, with different parameter modes.
+    Modes,
+    /// The same operation and modes, with different operands.
+    Operands,
+    /// A data cell with a different value.
+    Data,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeKind::Added => write!(f, "added"),
+            ChangeKind::Removed => write!(f, "removed"),
+            ChangeKind::Modes => write!(f, "modes"),
+            ChangeKind::Operands => write!(f, "operands"),
+            ChangeKind::Data => write!(f, "data"),
+        }
+    }
+}
+
+/// A single difference between two programs. Addresses are None on the
+/// side where the change is missing.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Change {
+    pub kind: ChangeKind,
+
+    /// Whether the words are an instruction, rather than a data cell.
+    pub instruction: bool,
+    pub left: Option<IntMem>,
+    pub right: Option<IntMem>,
+    pub before: Vec<IntMem>,
+    pub after: Vec<IntMem>,
+}
+
+// Format words as assembly when they are an instruction,
+// or as a plain value when they are data.
+fn describe(words: &[IntMem], instruction: bool) -> String {
+    let opcode = match OpCode::new(words[0]) {
+        Ok(o) if instruction => o,
+        _ => return format!("{}", words[0]),
+    };
+
+    let mut text = format!("{}", opcode.op());
+    for (i, arg) in words.iter().skip(1).enumerate() {
+        match opcode.mode(i as u32 + 1) {
+            Ok(ParameterMode::Immediate) => text.push_str(&format!(",{}", arg)),
+            Ok(ParameterMode::Position) => text.push_str(&format!(",&{}", arg)),
+            Ok(ParameterMode::Relative) => text.push_str(&format!(",${}", arg)),
+            Err(_) => text.push_str(&format!(",?{}", arg)),
+        }
+    }
+    text
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let address = |a: Option<IntMem>| match a {
+            Some(a) => format!("{:04}", a),
+            None => String::from("    "),
+        };
+        let sign = match self.kind {
+            ChangeKind::Added => '+',
+            ChangeKind::Removed => '-',
+            _ => '~',
+        };
+
+        write!(
+            f,
+            "{} {} {}  ",
+            sign,
+            address(self.left),
+            address(self.right)
+        )?;
+        match self.kind {
+            ChangeKind::Added => write!(f, "{}", describe(&self.after, self.instruction)),
+            ChangeKind::Removed => write!(f, "{}", describe(&self.before, self.instruction)),
+            _ => write!(
+                f,
+                "{} -> {} ({})",
+                describe(&self.before, self.instruction),
+                describe(&self.after, self.instruction),
+                self.kind
+            ),
+        }
+    }
+}
+
+/// Every difference found between two programs.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Diff {
+    changes: Vec<Change>,
+}
+
+impl Diff {
+    pub fn new<L, R>(left: &L, right: &R) -> Self
+    where
+        L: Arguments,
+        R: Arguments,
+    {
+        Diff::tapes(&left.tape(), &right.tape())
+    }
+
+    pub fn tapes(left: &[IntMem], right: &[IntMem]) -> Self {
+        let left = units(left);
+        let right = units(right);
+        let mut changes = Vec::new();
+
+        for (l, r) in align(&left, &right) {
+            let change = match (l, r) {
+                (Some(l), Some(r)) if l.words == r.words => continue,
+                (Some(l), Some(r)) => {
+                    let kind = if !l.instruction {
+                        ChangeKind::Data
+                    } else if l.words[0] != r.words[0] {
+                        ChangeKind::Modes
+                    } else {
+                        ChangeKind::Operands
+                    };
+                    Change {
+                        kind,
+                        instruction: l.instruction,
+                        left: Some(l.address),
+                        right: Some(r.address),
+                        before: l.words.clone(),
+                        after: r.words.clone(),
+                    }
+                }
+                (Some(l), None) => Change {
+                    kind: ChangeKind::Removed,
+                    instruction: l.instruction,
+                    left: Some(l.address),
+                    right: None,
+                    before: l.words.clone(),
+                    after: Vec::new(),
+                },
+                (None, Some(r)) => Change {
+                    kind: ChangeKind::Added,
+                    instruction: r.instruction,
+                    left: None,
+                    right: Some(r.address),
+                    before: Vec::new(),
+                    after: r.words.clone(),
+                },
+                (None, None) => continue,
+            };
+            changes.push(change);
+        }
+
+        Diff { changes }
+    }
+
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The changes as a JSON array of objects.
+    pub fn to_json(&self) -> String {
+        let address = |a: Option<IntMem>| match a {
+            Some(a) => format!("{}", a),
+            None => String::from("null"),
+        };
+        let words = |w: &[IntMem]| {
+            let w: Vec<String> = w.iter().map(|v| format!("{}", v)).collect();
+            format!("[{}]", w.join(","))
+        };
+
+        let changes: Vec<String> = self
+            .changes
+            .iter()
+            .map(|c| {
+                format!(
+                    "{{\"kind\":\"{}\",\"instruction\":{},\"left\":{},\"right\":{},\"before\":{},\"after\":{}}}",
+                    c.kind,
+                    c.instruction,
+                    address(c.left),
+                    address(c.right),
+                    words(&c.before),
+                    words(&c.after)
+                )
+            })
+            .collect();
+        format!("[{}]", changes.join(","))
+    }
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in self.changes.iter() {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+// Pair up units using the longest common subsequence of their keys.
+// Unchanged runs at either end are paired directly, which keeps the
+// table small when only part of a program differs.
+fn align<'u>(left: &'u [Unit], right: &'u [Unit]) -> Vec<(Option<&'u Unit>, Option<&'u Unit>)> {
+    let prefix = left
+        .iter()
+        .zip(right.iter())
+        .take_while(|(l, r)| l.words == r.words)
+        .count();
+    let suffix = left[prefix..]
+        .iter()
+        .rev()
+        .zip(right[prefix..].iter().rev())
+        .take_while(|(l, r)| l.words == r.words)
+        .count();
+
+    let l = &left[prefix..left.len() - suffix];
+    let r = &right[prefix..right.len() - suffix];
+
+    // lengths[i][j] is the length of the longest common subsequence
+    // of l[i..] and r[j..].
+    let width = r.len() + 1;
+    let mut lengths = vec![0u32; (l.len() + 1) * width];
+    for i in (0..l.len()).rev() {
+        for j in (0..r.len()).rev() {
+            lengths[i * width + j] = if l[i].key() == r[j].key() {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut pairs: Vec<_> = left[..prefix]
+        .iter()
+        .zip(right[..prefix].iter())
+        .map(|(l, r)| (Some(l), Some(r)))
+        .collect();
+
+    let (mut i, mut j) = (0, 0);
+    while i < l.len() || j < r.len() {
+        if i < l.len() && j < r.len() && l[i].key() == r[j].key() {
+            pairs.push((Some(&l[i]), Some(&r[j])));
+            i += 1;
+            j += 1;
+        } else if i < l.len()
+            && (j == r.len() || lengths[(i + 1) * width + j] >= lengths[i * width + j + 1])
+        {
+            pairs.push((Some(&l[i]), None));
+            i += 1;
+        } else {
+            pairs.push((None, Some(&r[j])));
+            j += 1;
+        }
+    }
+
+    pairs.extend(
+        left[left.len() - suffix..]
+            .iter()
+            .zip(right[right.len() - suffix..].iter())
+            .map(|(l, r)| (Some(l), Some(r))),
+    );
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Program;
+
+    #[test]
+    fn identical() {
+        let program: Program = vec![1101, 1, 2, 7, 4, 7, 99, 0].into();
+        assert!(Diff::new(&program, &program).is_empty());
+    }
+
+    #[test]
+    fn changed_modes_operands_and_data() {
+        let left: Program = vec![1101, 1, 2, 9, 1001, 9, 3, 9, 99, 0].into();
+        let right: Program = vec![1101, 1, 5, 9, 101, 9, 3, 9, 99, 4].into();
+        let diff = Diff::new(&left, &right);
+
+        let kinds: Vec<ChangeKind> = diff.changes().iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![ChangeKind::Operands, ChangeKind::Modes, ChangeKind::Data]
+        );
+        assert_eq!(
+            format!("{}", diff),
+            "~ 0000 0000  Add,1,2,&9 -> Add,1,5,&9 (operands)\n\
+             ~ 0004 0004  Add,&9,3,&9 -> Add,9,&3,&9 (modes)\n\
+             ~ 0009 0009  0 -> 4 (data)\n"
+        );
+    }
+
+    #[test]
+    fn inserted_instruction() {
+        // The right program outputs an extra value before the same code,
+        // so everything after it has moved along by two.
+        let left: Program = vec![1101, 1, 2, 9, 4, 9, 99, 0, 0, 0].into();
+        let right: Program = vec![104, 7, 1101, 1, 2, 9, 4, 9, 99, 0, 0, 0].into();
+        let diff = Diff::new(&left, &right);
+
+        assert_eq!(diff.changes().len(), 1);
+        let change = &diff.changes()[0];
+        assert_eq!(change.kind, ChangeKind::Added);
+        assert_eq!(change.right, Some(0));
+        assert_eq!(
+            diff.to_json(),
+            "[{\"kind\":\"added\",\"instruction\":true,\"left\":null,\"right\":0,\"before\":[],\"after\":[104,7]}]"
+        );
+    }
+}
@@ -1,7 +1,16 @@
-pub use crate::errors::{IntcodeError, Result};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::device::{Device, Mapping};
+pub use crate::errors::{IntcodeError, Result, RuntimeError};
+use crate::history::History;
+use crate::hook::{Control, Hook};
 pub use crate::opcode::OpCode;
-use crate::opcode::OpCodeResult;
+use crate::opcode::{OpCodeResult, ParameterMode};
 pub use crate::program::{Arguments, Memory, Program};
+use crate::segment::Segment;
+use crate::trace::Trace;
 use crate::IntMem;
 
 #[derive(Debug, Eq, PartialEq)]
@@ -17,6 +26,13 @@ pub struct Computer {
     pc: IntMem,
     memory: Memory,
     pub(crate) input: Option<IntMem>,
+    history: Option<History>,
+    trace: Trace,
+    hooks: Vec<Box<dyn Hook>>,
+
+    // The instruction which is waiting for input, whose hooks
+    // have already been called.
+    stalled: Option<IntMem>,
 }
 
 impl Computer {
@@ -25,9 +41,38 @@ impl Computer {
             pc: 0,
             memory: Memory::new(program.into()),
             input: None,
+            history: None,
+            trace: Trace::default(),
+            hooks: Vec::new(),
+            stalled: None,
         }
     }
 
+    /// Record an undo log while running, so that execution can be
+    /// stepped backwards. When a capacity is given, only that many
+    /// of the most recent instructions are remembered.
+    pub fn with_history(mut self, capacity: Option<usize>) -> Self {
+        self.history = Some(History::new(capacity));
+        self
+    }
+
+    /// Remember this many of the most recently executed instructions,
+    /// to be reported alongside runtime errors. Defaults to 16.
+    pub fn with_trace(mut self, length: usize) -> Self {
+        self.trace = Trace::new(length);
+        self
+    }
+
+    /// Attach an instrumentation hook, which is called as instructions run.
+    /// Hooks are called in the order they were attached.
+    pub fn with_hook<H>(mut self, hook: H) -> Self
+    where
+        H: Hook + 'static,
+    {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
     pub fn run(&mut self) -> Result<CPUState> {
         loop {
             let state = self.op()?;
@@ -53,12 +98,68 @@ impl Computer {
         Follower { cpu: self }
     }
 
+    /// Execute a single instruction. Errors are wrapped in
+    /// [IntcodeError::Runtime], describing where they happened.
     pub fn op(&mut self) -> Result<CPUState> {
-        let opcode = OpCode::new(self.memory.argument(self.pc)?)?;
+        let pc = self.pc;
+        let instruction = self.memory.get(pc);
 
-        let result = opcode.operate(self);
+        let resumed = self.stalled.take() == Some(pc);
+        if let (false, false, Some(word)) = (self.hooks.is_empty(), resumed, instruction) {
+            let mut control = Control::Continue;
+            for hook in self.hooks.iter_mut() {
+                if hook.before_instruction(pc, word) == Control::Break {
+                    control = Control::Break;
+                }
+            }
+            if control == Control::Break {
+                return Err(IntcodeError::Breakpoint { pc });
+            }
+        }
 
-        match result {
+        if let Some(history) = self.history.as_mut() {
+            history.begin(pc, self.memory.stack_pointer(), self.input);
+        }
+
+        let state = self.execute();
+
+        if let Some(history) = self.history.as_mut() {
+            match state {
+                Ok(CPUState::Continue) => history.commit(None),
+                Ok(CPUState::Output(o)) => history.commit(Some(o)),
+                _ => history.abort(),
+            }
+        }
+
+        match state {
+            Ok(CPUState::Input) => {
+                self.stalled = Some(pc);
+                Ok(CPUState::Input)
+            }
+            Ok(state) => {
+                self.trace.push(pc, instruction.unwrap_or_default());
+                for hook in self.hooks.iter_mut() {
+                    if let CPUState::Output(value) = state {
+                        hook.output(value);
+                    }
+                    hook.after_instruction(pc, &state);
+                }
+                Ok(state)
+            }
+            Err(error) => Err(IntcodeError::Runtime(Box::new(RuntimeError {
+                error,
+                pc,
+                instruction,
+                relative_base: self.memory.stack_pointer(),
+                recent: self.trace.entries(),
+            }))),
+        }
+    }
+
+    fn execute(&mut self) -> Result<CPUState> {
+        let opcode = OpCode::new(self.memory.argument(self.pc)?)?;
+
+        match opcode.operate(self) {
             Ok(OpCodeResult::Advance(n)) => {
                 self.pc += n;
                 Ok(CPUState::Continue)
@@ -77,25 +178,154 @@ impl Computer {
         }
     }
 
+    /// Protect memory by dividing it into segments. Once segments are set,
+    /// writes must land in a writable segment, and reads of uninitialised
+    /// memory must fall within a segment.
+    pub fn with_segments(mut self, segments: Vec<Segment>) -> Self {
+        self.memory.set_segments(segments);
+        self
+    }
+
+    /// Attach a host device to a range of memory. Loads and saves in that
+    /// range are handled by the device instead of program memory. Side
+    /// effects on devices are not undone when stepping backwards.
+    pub fn with_device<D>(mut self, range: Range<IntMem>, device: D) -> Self
+    where
+        D: Device + 'static,
+    {
+        self.memory.attach(Mapping::new(range, Box::new(device)));
+        self
+    }
+
+    /// Address of the next instruction to run.
+    pub fn pc(&self) -> IntMem {
+        self.pc
+    }
+
+    /// The value stored at an address, bypassing any attached devices.
+    pub fn peek(&self, address: IntMem) -> Option<IntMem> {
+        self.memory.get(address)
+    }
+
+    /// A copy of program memory, from address 0 up to the highest
+    /// address which has been written.
+    pub fn snapshot(&self) -> Vec<IntMem> {
+        self.memory.tape()
+    }
+
+    /// The memory layout enforced by this computer.
+    pub fn segments(&self) -> &[Segment] {
+        self.memory.segments()
+    }
+
+    /// Number of instructions which can currently be undone.
+    pub fn history_len(&self) -> usize {
+        self.history.as_ref().map(|h| h.len()).unwrap_or(0)
+    }
+
+    /// Undo the most recently executed instruction.
+    pub fn step_back(&mut self) -> Result<()> {
+        self.rewind(1)
+    }
+
+    /// Run backwards to just before the most recent instruction which
+    /// wrote to this address. Executing the next instruction will repeat
+    /// that write.
+    pub fn rewind_to_write(&mut self, address: IntMem) -> Result<()> {
+        let n = self
+            .history
+            .as_ref()
+            .and_then(|h| h.distance_to_write(address))
+            .ok_or(IntcodeError::HistoryExhausted)?;
+        self.rewind(n)
+    }
+
+    /// Run backwards to just before the most recent output, returning the
+    /// value which will be output again by the next instruction.
+    pub fn rewind_to_output(&mut self) -> Result<IntMem> {
+        let n = self
+            .history
+            .as_ref()
+            .and_then(|h| h.distance_to_output())
+            .ok_or(IntcodeError::HistoryExhausted)?;
+        self.rewind(n)?;
+
+        // The output instruction has just been undone, so the memory
+        // it reads from is as it was when the output was produced.
+        let opcode = OpCode::new(self.memory.argument(self.pc)?)?;
+        self.load(&opcode, 1)
+    }
+
+    fn rewind(&mut self, n: usize) -> Result<()> {
+        let history = self
+            .history
+            .as_mut()
+            .ok_or(IntcodeError::HistoryExhausted)?;
+        if history.len() < n {
+            return Err(IntcodeError::HistoryExhausted);
+        }
+
+        self.stalled = None;
+        for _ in 0..n {
+            let step = history.pop().ok_or(IntcodeError::HistoryExhausted)?;
+            for (address, value) in step.writes.into_iter().rev() {
+                self.memory.restore(address, value);
+            }
+            self.pc = step.pc;
+            self.memory.set_stack_pointer(step.stack_pointer);
+            self.input = step.input;
+        }
+        Ok(())
+    }
+
     pub(crate) fn offset(&mut self, offset: IntMem) -> Result<()> {
         self.memory.offset(offset)
     }
 
     pub(crate) fn load(&mut self, opcode: &OpCode, parameter: u32) -> Result<IntMem> {
-        self.memory
-            .load(self.pc + (parameter as IntMem), opcode.mode(parameter)?)
+        let address = self.pc + (parameter as IntMem);
+        let mode = opcode.mode(parameter)?;
+        let value = self.memory.load(self.pc, address, mode)?;
+
+        if !self.hooks.is_empty() && mode != ParameterMode::Immediate {
+            let target = self.memory.target(address, mode)?;
+            for hook in self.hooks.iter_mut() {
+                hook.read(target, value);
+            }
+        }
+
+        Ok(value)
     }
 
     pub(crate) fn save(&mut self, opcode: &OpCode, parameter: u32, value: IntMem) -> Result<()> {
-        self.memory.save(
-            self.pc + (parameter as IntMem),
-            opcode.mode(parameter)?,
-            value,
-        )?;
+        let address = self.pc + (parameter as IntMem);
+        let mode = opcode.mode(parameter)?;
+
+        if let Some(history) = self.history.as_mut() {
+            let target = self.memory.target(address, mode)?;
+            history.write(target, self.memory.get(target));
+        }
+
+        self.memory.save(self.pc, address, mode, value)?;
+
+        if !self.hooks.is_empty() {
+            let target = self.memory.target(address, mode)?;
+            for hook in self.hooks.iter_mut() {
+                hook.write(target, value);
+            }
+        }
 
         Ok(())
     }
 
+    pub(crate) fn take_input(&mut self) -> Result<IntMem> {
+        let value = self.input.take().ok_or(IntcodeError::NoInput)?;
+        for hook in self.hooks.iter_mut() {
+            hook.input(value);
+        }
+        Ok(value)
+    }
+
     pub fn simple(&mut self, input: IntMem) -> Result<IntMem> {
         self.feed(input)?;
         self.follow().one()
@@ -0,0 +1,319 @@
+//! Memory-mapped devices, which let host code answer loads and saves
+//! to a range of Intcode memory.
+
+use alloc::boxed::Box;
+use core::fmt::Debug;
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+#[cfg(feature = "std")]
+use std::convert::TryFrom;
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "std")]
+use std::time;
+
+#[cfg(feature = "std")]
+use crate::errors::{IntcodeError, Result};
+use crate::IntMem;
+
+/// A host peripheral attached to a range of memory.
+///
+/// Offsets are relative to the start of the range the device
+/// is attached to.
+pub trait Device: Debug + Send {
+    /// Produce the value read by the program from this offset.
+    fn load(&mut self, offset: IntMem) -> IntMem;
+
+    /// Accept a value written by the program to this offset.
+    fn save(&mut self, offset: IntMem, value: IntMem);
+}
+
+/// A device along with the addresses it responds to.
+#[derive(Debug)]
+pub(crate) struct Mapping {
+    range: Range<IntMem>,
+    device: Box<dyn Device>,
+}
+
+impl Mapping {
+    pub(crate) fn new(range: Range<IntMem>, device: Box<dyn Device>) -> Self {
+        Mapping { range, device }
+    }
+
+    pub(crate) fn contains(&self, address: IntMem) -> bool {
+        self.range.contains(&address)
+    }
+
+    pub(crate) fn load(&mut self, address: IntMem) -> IntMem {
+        self.device.load(address - self.range.start)
+    }
+
+    pub(crate) fn save(&mut self, address: IntMem, value: IntMem) {
+        self.device.save(address - self.range.start, value)
+    }
+}
+
+#[cfg(feature = "std")]
+/// A grid of pixels, stored row by row, which the host
+/// can inspect through a [FramebufferHandle].
+#[derive(Debug)]
+pub struct Framebuffer {
+    width: IntMem,
+    height: IntMem,
+    pixels: Arc<Mutex<Vec<IntMem>>>,
+}
+
+#[cfg(feature = "std")]
+impl Framebuffer {
+    /// Both dimensions must be positive, and small enough for every
+    /// pixel to have an address.
+    pub fn new(width: IntMem, height: IntMem) -> Result<Self> {
+        let size = Some(width)
+            .filter(|_| width > 0 && height > 0)
+            .and_then(|w| w.checked_mul(height))
+            .and_then(|s| usize::try_from(s).ok())
+            .ok_or(IntcodeError::InvalidDeviceSize { width, height })?;
+
+        Ok(Framebuffer {
+            width,
+            height,
+            pixels: Arc::new(Mutex::new(vec![0; size])),
+        })
+    }
+
+    /// Number of addresses this framebuffer should be attached to.
+    pub fn size(&self) -> IntMem {
+        self.width * self.height
+    }
+
+    pub fn handle(&self) -> FramebufferHandle {
+        FramebufferHandle {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Device for Framebuffer {
+    fn load(&mut self, offset: IntMem) -> IntMem {
+        let pixels = self.pixels.lock().unwrap();
+        pixels.get(offset as usize).copied().unwrap_or(0)
+    }
+
+    fn save(&mut self, offset: IntMem, value: IntMem) {
+        let mut pixels = self.pixels.lock().unwrap();
+        if let Some(p) = pixels.get_mut(offset as usize) {
+            *p = value;
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+/// Host side of a [Framebuffer].
+#[derive(Debug, Clone)]
+pub struct FramebufferHandle {
+    width: IntMem,
+    height: IntMem,
+    pixels: Arc<Mutex<Vec<IntMem>>>,
+}
+
+#[cfg(feature = "std")]
+impl FramebufferHandle {
+    pub fn width(&self) -> IntMem {
+        self.width
+    }
+
+    pub fn height(&self) -> IntMem {
+        self.height
+    }
+
+    pub fn get(&self, x: IntMem, y: IntMem) -> Option<IntMem> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        let pixels = self.pixels.lock().unwrap();
+        pixels.get((y * self.width + x) as usize).copied()
+    }
+
+    /// A copy of every pixel, row by row.
+    pub fn pixels(&self) -> Vec<IntMem> {
+        self.pixels.lock().unwrap().clone()
+    }
+}
+
+#[cfg(feature = "std")]
+/// Reads as the number of milliseconds since the clock was created.
+/// Writes are ignored.
+#[derive(Debug)]
+pub struct Clock {
+    start: time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl Clock {
+    pub fn new() -> Self {
+        Clock {
+            start: time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for Clock {
+    fn default() -> Self {
+        Clock::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Device for Clock {
+    fn load(&mut self, _offset: IntMem) -> IntMem {
+        self.start.elapsed().as_millis() as IntMem
+    }
+
+    fn save(&mut self, _offset: IntMem, _value: IntMem) {}
+}
+
+/// Reads as a new non-negative pseudo-random number each time.
+/// Writing a value reseeds the generator.
+#[derive(Debug)]
+pub struct RandomPort {
+    state: u64,
+}
+
+impl RandomPort {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift gets stuck on zero.
+        RandomPort { state: seed.max(1) }
+    }
+}
+
+impl Device for RandomPort {
+    fn load(&mut self, _offset: IntMem) -> IntMem {
+        // xorshift64*
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        (self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 1) as IntMem
+    }
+
+    fn save(&mut self, _offset: IntMem, value: IntMem) {
+        self.state = (value as u64).max(1);
+    }
+}
+
+#[cfg(feature = "std")]
+/// A buffer of key presses from the host.
+///
+/// Offset 0 reads the next key (or 0 when no key is waiting),
+/// and offset 1 reads the number of keys waiting.
+#[derive(Debug, Default)]
+pub struct Keyboard {
+    buffer: Arc<Mutex<VecDeque<IntMem>>>,
+}
+
+#[cfg(feature = "std")]
+impl Keyboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of addresses this keyboard should be attached to.
+    pub fn size(&self) -> IntMem {
+        2
+    }
+
+    pub fn handle(&self) -> KeyboardHandle {
+        KeyboardHandle {
+            buffer: self.buffer.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Device for Keyboard {
+    fn load(&mut self, offset: IntMem) -> IntMem {
+        let mut buffer = self.buffer.lock().unwrap();
+        match offset {
+            0 => buffer.pop_front().unwrap_or(0),
+            1 => buffer.len() as IntMem,
+            _ => 0,
+        }
+    }
+
+    fn save(&mut self, _offset: IntMem, _value: IntMem) {}
+}
+
+#[cfg(feature = "std")]
+/// Host side of a [Keyboard].
+#[derive(Debug, Clone)]
+pub struct KeyboardHandle {
+    buffer: Arc<Mutex<VecDeque<IntMem>>>,
+}
+
+#[cfg(feature = "std")]
+impl KeyboardHandle {
+    pub fn press(&self, key: IntMem) {
+        self.buffer.lock().unwrap().push_back(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Computer;
+
+    #[test]
+    fn framebuffer() {
+        let fb = Framebuffer::new(2, 2).unwrap();
+        let handle = fb.handle();
+
+        // Write 7 to pixel (1, 1), then read it back into address 13.
+        let program = vec![1101, 3, 4, 103, 1001, 103, 0, 13, 99, 0, 0, 0, 0, 0];
+        let mut cpu = Computer::new(program).with_device(100..100 + fb.size(), fb);
+        cpu.run().unwrap();
+
+        assert_eq!(handle.get(1, 1), Some(7));
+        assert_eq!(handle.get(0, 0), Some(0));
+        assert_eq!(handle.get(2, 0), None);
+        assert_eq!(cpu.program().get(13), Some(7));
+    }
+
+    #[test]
+    fn framebuffer_size() {
+        for (width, height) in [(0, 2), (-2, -2), (IntMem::MAX, 2)].iter() {
+            assert!(matches!(
+                Framebuffer::new(*width, *height),
+                Err(IntcodeError::InvalidDeviceSize { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn keyboard() {
+        let keyboard = Keyboard::new();
+        let handle = keyboard.handle();
+        handle.press(65);
+
+        let program = vec![4, 51, 4, 50, 4, 50, 99];
+        let mut cpu = Computer::new(program).with_device(50..52, keyboard);
+        assert_eq!(cpu.follow().collect::<Vec<IntMem>>(), vec![1, 65, 0]);
+    }
+
+    #[test]
+    fn random() {
+        let program = vec![4, 20, 4, 20, 99];
+        let mut cpu = Computer::new(program.clone()).with_device(20..21, RandomPort::new(3));
+        let first = cpu.follow().collect::<Vec<IntMem>>();
+
+        let mut cpu = Computer::new(program).with_device(20..21, RandomPort::new(3));
+        let second = cpu.follow().collect::<Vec<IntMem>>();
+
+        assert_eq!(first, second);
+        assert_ne!(first[0], first[1]);
+        assert!(first.iter().all(|&v| v >= 0));
+    }
+}
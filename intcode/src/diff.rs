@@ -0,0 +1,62 @@
+use anyhow::Error;
+use clap::{App, Arg};
+use intcode::{Diff, Program};
+use std::fs::File;
+
+type Result<T> = std::result::Result<T, Error>;
+
+fn program(filename: &str) -> Result<Program> {
+    let reader: Box<dyn ::std::io::Read + 'static> = match filename {
+        "-" => Box::new(::std::io::stdin()),
+        path => {
+            let f: File = File::open(path)?;
+            Box::new(f)
+        }
+    };
+
+    Program::read(reader)
+}
+
+fn main() -> Result<()> {
+    let matches = App::new("Advent of Code 2019")
+        .version("1.0")
+        .author("Alex Rudy <opensource@alexrudy.net>")
+        .about("Compare Intcode Programs instruction by instruction")
+        .arg(
+            Arg::with_name("json")
+                .long("json")
+                .help("Write the differences as JSON")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("left")
+                .value_name("LEFT")
+                .required(true)
+                .takes_value(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("right")
+                .value_name("RIGHT")
+                .required(true)
+                .takes_value(true)
+                .index(2),
+        )
+        .get_matches();
+
+    let left = program(matches.value_of("left").unwrap())?;
+    let right = program(matches.value_of("right").unwrap())?;
+    let diff = Diff::new(&left, &right);
+
+    if matches.is_present("json") {
+        println!("{}", diff.to_json());
+    } else {
+        print!("{}", diff);
+    }
+
+    // Like diff(1), exit with a failure status when the programs differ.
+    if !diff.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -1,39 +1,199 @@
-use thiserror::Error;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Write;
 
 use crate::opcode::ParameterMode;
+use crate::segment::SegmentKind;
+use crate::trace::Executed;
 use crate::IntMem;
 
-#[derive(Error, Debug)]
+#[derive(Debug)]
 pub enum IntcodeError {
-    #[error("Invalid program counter position: {0}")]
     InvalidPosition(IntMem),
 
-    #[error("Unknown opcode: {0}")]
     UnknownOpcode(IntMem),
 
-    #[error("Invalid Parameter Mode for opcode: {0}, parameter: {1}")]
     InvalidParameterMode(IntMem, u32),
 
-    #[error("Illegal parameter mode {0:?}")]
     IllegalParameterMode(ParameterMode),
 
-    #[error("Missing Parameters for offset {0} at position {1}")]
     MissingParameters(IntMem, IntMem),
 
-    #[error("Invalid address {0}")]
     InvalidAddress(IntMem),
 
-    #[error("No input avaialbe")]
     NoInput,
 
-    #[error("Input is already present")]
     InputAlreadyPresent,
 
-    #[error("No output avaialbe")]
     NoOutput,
 
-    #[error("Unexpected output")]
     UnexpectedOutput,
+
+    HistoryExhausted,
+
+    WriteProtected {
+        pc: IntMem,
+        address: IntMem,
+        segment: SegmentKind,
+    },
+
+    ReadProtected {
+        pc: IntMem,
+        address: IntMem,
+        segment: SegmentKind,
+    },
+
+    SegmentationFault {
+        pc: IntMem,
+        address: IntMem,
+    },
+
+    PatchMismatch {
+        address: IntMem,
+        expected: IntMem,
+        found: IntMem,
+    },
+
+    Runtime(Box<RuntimeError>),
+
+    Breakpoint {
+        pc: IntMem,
+    },
+
+    InvalidDeviceSize {
+        width: IntMem,
+        height: IntMem,
+    },
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntcodeError::InvalidPosition(pc) => {
+                write!(f, "Invalid program counter position: {}", pc)
+            }
+            IntcodeError::UnknownOpcode(code) => write!(f, "Unknown opcode: {}", code),
+            IntcodeError::InvalidParameterMode(code, parameter) => write!(
+                f,
+                "Invalid Parameter Mode for opcode: {}, parameter: {}",
+                code, parameter
+            ),
+            IntcodeError::IllegalParameterMode(mode) => {
+                write!(f, "Illegal parameter mode {:?}", mode)
+            }
+            IntcodeError::MissingParameters(offset, position) => write!(
+                f,
+                "Missing Parameters for offset {} at position {}",
+                offset, position
+            ),
+            IntcodeError::InvalidAddress(address) => write!(f, "Invalid address {}", address),
+            IntcodeError::NoInput => write!(f, "No input avaialbe"),
+            IntcodeError::InputAlreadyPresent => write!(f, "Input is already present"),
+            IntcodeError::NoOutput => write!(f, "No output avaialbe"),
+            IntcodeError::UnexpectedOutput => write!(f, "Unexpected output"),
+            IntcodeError::HistoryExhausted => write!(f, "Execution history is exhausted"),
+            IntcodeError::WriteProtected {
+                pc,
+                address,
+                segment,
+            } => write!(
+                f,
+                "Write to read-only {} segment at address {} (pc {})",
+                segment, address, pc
+            ),
+            IntcodeError::ReadProtected {
+                pc,
+                address,
+                segment,
+            } => write!(
+                f,
+                "Read from unreadable {} segment at address {} (pc {})",
+                segment, address, pc
+            ),
+            IntcodeError::SegmentationFault { pc, address } => {
+                write!(f, "Segmentation fault at address {} (pc {})", address, pc)
+            }
+            IntcodeError::PatchMismatch {
+                address,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Patch expected {} at address {}, found {}",
+                expected, address, found
+            ),
+            IntcodeError::Runtime(e) => write!(f, "{}", e),
+            IntcodeError::Breakpoint { pc } => write!(f, "Stopped at breakpoint (pc {})", pc),
+            IntcodeError::InvalidDeviceSize { width, height } => {
+                write!(f, "Invalid device size {}x{}", width, height)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IntcodeError {}
+
+impl IntcodeError {
+    /// The underlying error, without any runtime context.
+    pub fn root(&self) -> &IntcodeError {
+        match self {
+            IntcodeError::Runtime(e) => &e.error,
+            e => e,
+        }
+    }
+}
+
+/// An error raised while running a program, along with the state
+/// of the computer when it happened.
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub error: IntcodeError,
+    pub pc: IntMem,
+
+    /// The instruction word at the program counter, if there is one.
+    pub instruction: Option<IntMem>,
+    pub relative_base: IntMem,
+
+    /// The instructions which ran just before the error, oldest first.
+    pub recent: Vec<Executed>,
+}
+
+impl RuntimeError {
+    /// A multi-line description of the error and how the program reached it.
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        writeln!(report, "{}", self.error).unwrap();
+        writeln!(report, "  pc:            {}", self.pc).unwrap();
+        match self.instruction {
+            Some(i) => writeln!(report, "  instruction:   {}", i).unwrap(),
+            None => writeln!(report, "  instruction:   (none)").unwrap(),
+        }
+        writeln!(report, "  relative base: {}", self.relative_base).unwrap();
+
+        if !self.recent.is_empty() {
+            writeln!(report, "  recent instructions, oldest first:").unwrap();
+            for executed in self.recent.iter() {
+                writeln!(report, "    {}", executed).unwrap();
+            }
+        }
+        report
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (pc {})", self.error, self.pc)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RuntimeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
 }
 
-pub type Result<T> = ::std::result::Result<T, IntcodeError>;
+pub type Result<T> = ::core::result::Result<T, IntcodeError>;
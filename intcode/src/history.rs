@@ -0,0 +1,162 @@
+//! Undo log for stepping a Computer backwards through execution.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::IntMem;
+
+/// Everything needed to undo a single instruction.
+#[derive(Debug, Clone)]
+pub(crate) struct Step {
+    pub(crate) pc: IntMem,
+    pub(crate) stack_pointer: IntMem,
+    pub(crate) input: Option<IntMem>,
+    pub(crate) writes: Vec<(IntMem, Option<IntMem>)>,
+    pub(crate) output: Option<IntMem>,
+}
+
+impl Step {
+    fn wrote(&self, address: IntMem) -> bool {
+        self.writes.iter().any(|(a, _)| *a == address)
+    }
+}
+
+/// Records the effects of each instruction, oldest first.
+///
+/// When a capacity is set, the oldest steps are forgotten
+/// once the log grows past it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct History {
+    steps: VecDeque<Step>,
+    capacity: Option<usize>,
+    pending: Option<Step>,
+}
+
+impl History {
+    pub(crate) fn new(capacity: Option<usize>) -> Self {
+        Self {
+            steps: VecDeque::new(),
+            capacity,
+            pending: None,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub(crate) fn begin(&mut self, pc: IntMem, stack_pointer: IntMem, input: Option<IntMem>) {
+        self.pending = Some(Step {
+            pc,
+            stack_pointer,
+            input,
+            writes: Vec::new(),
+            output: None,
+        });
+    }
+
+    pub(crate) fn write(&mut self, address: IntMem, previous: Option<IntMem>) {
+        if let Some(step) = self.pending.as_mut() {
+            step.writes.push((address, previous));
+        }
+    }
+
+    pub(crate) fn commit(&mut self, output: Option<IntMem>) {
+        if let Some(mut step) = self.pending.take() {
+            step.output = output;
+            self.steps.push_back(step);
+            if self.capacity.map(|c| self.steps.len() > c).unwrap_or(false) {
+                self.steps.pop_front();
+            }
+        }
+    }
+
+    pub(crate) fn abort(&mut self) {
+        self.pending = None;
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<Step> {
+        self.steps.pop_back()
+    }
+
+    /// Number of steps to undo to get back to just before the most
+    /// recent step matching the predicate.
+    fn distance<F>(&self, predicate: F) -> Option<usize>
+    where
+        F: Fn(&Step) -> bool,
+    {
+        self.steps.iter().rev().position(predicate).map(|n| n + 1)
+    }
+
+    pub(crate) fn distance_to_write(&self, address: IntMem) -> Option<usize> {
+        self.distance(|s| s.wrote(address))
+    }
+
+    pub(crate) fn distance_to_output(&self) -> Option<usize> {
+        self.distance(|s| s.output.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CPUState, Computer, IntcodeError};
+
+    #[test]
+    fn step_back() {
+        let mut cpu = Computer::new(vec![1, 0, 0, 0, 99]).with_history(None);
+        cpu.run().unwrap();
+        assert_eq!(cpu.history_len(), 1);
+
+        cpu.step_back().unwrap();
+        assert_eq!(cpu.program().get(0), Some(1));
+    }
+
+    #[test]
+    fn step_back_without_history() {
+        let mut cpu = Computer::new(vec![1, 0, 0, 0, 99]);
+        cpu.run().unwrap();
+        assert!(matches!(
+            cpu.step_back(),
+            Err(IntcodeError::HistoryExhausted)
+        ));
+    }
+
+    #[test]
+    fn rewind_to_output() {
+        let mut cpu = Computer::new(vec![3, 0, 4, 0, 99]).with_history(None);
+        assert_eq!(cpu.simple(5).unwrap(), 5);
+
+        assert_eq!(cpu.rewind_to_output().unwrap(), 5);
+        assert_eq!(cpu.op().unwrap(), CPUState::Output(5));
+    }
+
+    #[test]
+    fn rewind_to_write() {
+        let program = vec![1101, 1, 2, 9, 1101, 3, 4, 10, 99, 0, 0];
+        let mut cpu = Computer::new(program).with_history(None);
+        cpu.run().unwrap();
+
+        cpu.rewind_to_write(9).unwrap();
+        assert_eq!(cpu.history_len(), 0);
+
+        // Inputs which were consumed are restored when rewinding.
+        let mut cpu = Computer::new(vec![3, 5, 99]).with_history(None);
+        cpu.feed(7).unwrap();
+        cpu.run().unwrap();
+        cpu.rewind_to_write(5).unwrap();
+        assert!(matches!(
+            cpu.feed(8),
+            Err(IntcodeError::InputAlreadyPresent)
+        ));
+    }
+
+    #[test]
+    fn bounded_history() {
+        let program = vec![1101, 1, 2, 9, 1101, 3, 4, 10, 99, 0, 0];
+        let mut cpu = Computer::new(program).with_history(Some(1));
+        cpu.run().unwrap();
+
+        assert!(cpu.rewind_to_write(10).is_ok());
+        assert!(cpu.rewind_to_write(9).is_err());
+    }
+}
@@ -0,0 +1,172 @@
+//! Instrumentation hooks, which observe a Computer as it runs.
+
+use core::fmt::Debug;
+
+use crate::cpu::CPUState;
+use crate::IntMem;
+
+/// Whether execution should carry on after a hook has been called.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Control {
+    Continue,
+    Break,
+}
+
+/// Callbacks made while a computer executes instructions.
+///
+/// Every method does nothing by default, so hooks only need to implement
+/// the events they care about. Computers without hooks don't pay for them.
+pub trait Hook: Debug + Send {
+    /// Called before each instruction. Returning [Control::Break] stops
+    /// the computer before the instruction runs, and [Computer::op](crate::Computer::op)
+    /// returns [IntcodeError::Breakpoint](crate::IntcodeError::Breakpoint).
+    /// Hooks are asked again when execution resumes.
+    fn before_instruction(&mut self, _pc: IntMem, _instruction: IntMem) -> Control {
+        Control::Continue
+    }
+
+    /// Called once an instruction has run to completion.
+    fn after_instruction(&mut self, _pc: IntMem, _state: &CPUState) {}
+
+    /// Called when an instruction reads a parameter from memory.
+    fn read(&mut self, _address: IntMem, _value: IntMem) {}
+
+    /// Called when an instruction writes to memory.
+    fn write(&mut self, _address: IntMem, _value: IntMem) {}
+
+    /// Called when an input value is consumed by the program.
+    fn input(&mut self, _value: IntMem) {}
+
+    /// Called when the program outputs a value.
+    fn output(&mut self, _value: IntMem) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Computer, IntcodeError};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Default)]
+    struct Recorder {
+        events: Arc<Mutex<Vec<String>>>,
+        breakpoint: Option<IntMem>,
+    }
+
+    impl Hook for Recorder {
+        fn before_instruction(&mut self, pc: IntMem, instruction: IntMem) -> Control {
+            if self.breakpoint == Some(pc) {
+                self.breakpoint = None;
+                return Control::Break;
+            }
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("{}: {}", pc, instruction));
+            Control::Continue
+        }
+
+        fn read(&mut self, address: IntMem, value: IntMem) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("read {} = {}", address, value));
+        }
+
+        fn write(&mut self, address: IntMem, value: IntMem) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("write {} = {}", address, value));
+        }
+
+        fn input(&mut self, value: IntMem) {
+            self.events.lock().unwrap().push(format!("input {}", value));
+        }
+
+        fn output(&mut self, value: IntMem) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("output {}", value));
+        }
+    }
+
+    #[test]
+    fn record_events() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Recorder {
+            events: events.clone(),
+            breakpoint: None,
+        };
+
+        let mut cpu = Computer::new(vec![3, 0, 1001, 0, 1, 0, 4, 0, 99]).with_hook(recorder);
+        assert_eq!(cpu.simple(5).unwrap(), 6);
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                "0: 3",
+                "input 5",
+                "write 0 = 5",
+                "2: 1001",
+                "read 0 = 5",
+                "write 0 = 6",
+                "6: 4",
+                "read 0 = 6",
+                "output 6",
+                "8: 99",
+            ]
+        );
+    }
+
+    #[test]
+    fn stall_on_input() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Recorder {
+            events: events.clone(),
+            breakpoint: None,
+        };
+
+        let mut cpu = Computer::new(vec![3, 0, 4, 0, 99]).with_hook(recorder);
+        assert_eq!(cpu.op().unwrap(), CPUState::Input);
+        assert_eq!(cpu.op().unwrap(), CPUState::Input);
+        cpu.feed(7).unwrap();
+        assert_eq!(cpu.op().unwrap(), CPUState::Continue);
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec!["0: 3", "input 7", "write 0 = 7"]
+        );
+    }
+
+    #[test]
+    fn breakpoint_on_input() {
+        let recorder = Recorder {
+            breakpoint: Some(0),
+            ..Recorder::default()
+        };
+        let mut cpu = Computer::new(vec![3, 0, 4, 0, 99]).with_hook(recorder);
+
+        assert!(matches!(cpu.op(), Err(IntcodeError::Breakpoint { pc: 0 })));
+        assert_eq!(cpu.op().unwrap(), CPUState::Input);
+        cpu.feed(7).unwrap();
+        assert_eq!(cpu.op().unwrap(), CPUState::Continue);
+        assert_eq!(cpu.op().unwrap(), CPUState::Output(7));
+    }
+
+    #[test]
+    fn breakpoint() {
+        let recorder = Recorder {
+            breakpoint: Some(4),
+            ..Recorder::default()
+        };
+        let mut cpu = Computer::new(vec![1101, 1, 2, 0, 4, 0, 99]).with_hook(recorder);
+
+        assert!(matches!(cpu.op(), Ok(CPUState::Continue)));
+        assert!(matches!(cpu.op(), Err(IntcodeError::Breakpoint { pc: 4 })));
+
+        // The recorder only breaks once, so execution can resume.
+        assert!(matches!(cpu.op(), Ok(CPUState::Output(3))));
+    }
+}
@@ -1,12 +1,46 @@
+//! An Intcode virtual machine.
+//!
+//! The core of the machine only needs an allocator. Disable the default
+//! `std` feature to build it with `#![no_std]`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+mod actor;
+mod codegen;
+mod compare;
 mod cpu;
+mod device;
 mod errors;
+mod history;
+mod hook;
 mod opcode;
+mod optimizer;
+mod patch;
 mod program;
-
+mod scanner;
+mod segment;
+mod trace;
+
+#[cfg(feature = "std")]
+pub use crate::actor::{spawn_connected, Actor};
+pub use crate::codegen::{transpile, Language, ParseLanguageError};
+pub use crate::compare::{Change, ChangeKind, Diff};
 pub use crate::cpu::{CPUState, Computer};
-pub use crate::errors::{IntcodeError, Result};
+#[cfg(feature = "std")]
+pub use crate::device::{Clock, Framebuffer, FramebufferHandle, Keyboard, KeyboardHandle};
+pub use crate::device::{Device, RandomPort};
+pub use crate::errors::{IntcodeError, Result, RuntimeError};
+pub use crate::hook::{Control, Hook};
 pub use crate::opcode::OpCode;
+pub use crate::optimizer::{OptimizationReport, Optimizer};
+pub use crate::patch::{ParsePatchError, Patch, PatchEntry};
 pub use crate::program::{Arguments, Assembly, Program};
+pub use crate::scanner::{Condition, Scanner};
+pub use crate::segment::{Permissions, Segment, SegmentKind};
+pub use crate::trace::Executed;
 
 pub type IntMem = i64;
 
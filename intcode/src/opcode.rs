@@ -1,6 +1,8 @@
 // Implement Opcodes for Intcode
 
-use std::fmt;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::fmt;
 
 use crate::errors::{IntcodeError, Result};
 use crate::{Computer, IntMem};
@@ -77,6 +79,10 @@ impl OpCode {
         Ok(OpCode(code))
     }
 
+    pub(crate) fn word(&self) -> IntMem {
+        self.0
+    }
+
     pub(crate) fn op(&self) -> Op {
         Op::from_code(self.0).unwrap()
     }
@@ -138,7 +144,7 @@ impl OpCode {
     }
 
     fn input(&self, cpu: &mut Computer) -> Result<OpCodeResult> {
-        let value = cpu.input.take().ok_or(IntcodeError::NoInput)?;
+        let value = cpu.take_input()?;
         cpu.save(self, 1, value)?;
 
         Ok(OpCodeResult::Advance(self.n_arguments() as IntMem))
@@ -194,7 +200,6 @@ impl OpCode {
     }
 
     pub(crate) fn modes(&self) -> Result<Vec<ParameterMode>> {
-        use std::convert::TryInto;
         let n = self.n_arguments();
         let mut modes = Vec::with_capacity((n - 1).try_into().unwrap());
         for i in 1..n {
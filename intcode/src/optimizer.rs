@@ -0,0 +1,631 @@
+//! Peephole optimization of Intcode programs.
+//!
+//! The optimizer follows control flow from address 0 to find the code
+//! which can run, then rewrites instructions whose behavior is known
+//! before the program starts:
+//!
+//! - arithmetic and comparisons on two immediate operands are folded
+//!   into a single constant store,
+//! - conditional jumps on an immediate condition become unconditional
+//!   jumps, or are removed when they can never be taken,
+//! - code which can never run is removed, and the remaining program
+//!   is renumbered, when every address in the program is known.
+//!
+//! Any instruction whose memory is read as data or written to by the
+//! program is left untouched, and if the program rewrites its own
+//! control flow, nothing is changed at all.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use crate::errors::{IntcodeError, Result};
+use crate::opcode::{Op, OpCode, ParameterMode};
+use crate::program::{Arguments, Program};
+use crate::IntMem;
+
+/// A decoded instruction found while following control flow.
+#[derive(Debug, Clone)]
+pub(crate) struct Decoded {
+    pub(crate) pc: IntMem,
+    opcode: OpCode,
+    args: Vec<IntMem>,
+}
+
+impl Decoded {
+    fn decode(tape: &[IntMem], pc: IntMem) -> Result<Self> {
+        let word = |address: IntMem| {
+            if address < 0 {
+                return Err(IntcodeError::InvalidAddress(address));
+            }
+            tape.get(address as usize)
+                .copied()
+                .ok_or(IntcodeError::InvalidAddress(address))
+        };
+
+        let opcode = OpCode::new(word(pc)?)?;
+        opcode.modes()?;
+
+        let args = (1..(opcode.n_arguments() as IntMem))
+            .map(|i| word(pc + i))
+            .collect::<Result<Vec<IntMem>>>()?;
+
+        Ok(Decoded { pc, opcode, args })
+    }
+
+    pub(crate) fn word(&self) -> IntMem {
+        self.opcode.word()
+    }
+
+    pub(crate) fn len(&self) -> IntMem {
+        self.opcode.n_arguments() as IntMem
+    }
+
+    fn cells(&self) -> Range<IntMem> {
+        self.pc..(self.pc + self.len())
+    }
+
+    pub(crate) fn op(&self) -> Op {
+        self.opcode.op()
+    }
+
+    pub(crate) fn mode(&self, parameter: u32) -> ParameterMode {
+        self.opcode
+            .mode(parameter)
+            .expect("Modes are checked when decoding")
+    }
+
+    fn arg(&self, parameter: u32) -> IntMem {
+        self.args[(parameter - 1) as usize]
+    }
+
+    fn is_jump(&self) -> bool {
+        matches!(self.op(), Op::JumpIfTrue | Op::JumpIfFalse)
+    }
+
+    /// Parameters which are read from memory, and the one written to (if any).
+    fn parameters(&self) -> (Vec<u32>, Option<u32>) {
+        match self.op() {
+            Op::Add | Op::Mul | Op::LessThan | Op::EqualTo => (vec![1, 2], Some(3)),
+            Op::Input => (vec![], Some(1)),
+            Op::Output | Op::MoveStack => (vec![1], None),
+            Op::JumpIfTrue | Op::JumpIfFalse => (vec![1, 2], None),
+            Op::Halt => (vec![], None),
+        }
+    }
+
+    /// Whether a jump on an immediate condition is always (Some(true))
+    /// or never (Some(false)) taken.
+    fn taken(&self) -> Option<bool> {
+        if !self.is_jump() || self.mode(1) != ParameterMode::Immediate {
+            return None;
+        }
+        match self.op() {
+            Op::JumpIfTrue => Some(self.arg(1) != 0),
+            _ => Some(self.arg(1) == 0),
+        }
+    }
+
+    /// Addresses execution can continue at, and whether execution might
+    /// also continue at an address which can't be known ahead of time.
+    fn successors(&self) -> (Vec<IntMem>, bool) {
+        let next = self.pc + self.len();
+        match self.op() {
+            Op::Halt => (vec![], false),
+            Op::JumpIfTrue | Op::JumpIfFalse => {
+                let direct = self.mode(2) == ParameterMode::Immediate;
+                let mut targets = Vec::new();
+                if self.taken() != Some(true) {
+                    targets.push(next);
+                }
+                if self.taken() != Some(false) && direct {
+                    targets.push(self.arg(2));
+                }
+                (targets, self.taken() != Some(false) && !direct)
+            }
+            _ => (vec![next], false),
+        }
+    }
+
+    pub(crate) fn encode(&self) -> Vec<IntMem> {
+        let mut words = vec![self.opcode.word()];
+        words.extend(self.args.iter());
+        words
+    }
+}
+
+fn mode_digit(mode: ParameterMode) -> IntMem {
+    match mode {
+        ParameterMode::Position => 0,
+        ParameterMode::Immediate => 1,
+        ParameterMode::Relative => 2,
+    }
+}
+
+/// What the optimizer learned about a program by following its control flow.
+#[derive(Debug, Default)]
+pub(crate) struct Analysis {
+    pub(crate) instructions: BTreeMap<IntMem, Decoded>,
+    indirect: bool,
+    relative: bool,
+    overlapping: bool,
+    undecodable: bool,
+    written: BTreeSet<IntMem>,
+    frozen: BTreeSet<IntMem>,
+}
+
+impl Analysis {
+    pub(crate) fn new(tape: &[IntMem], stack_base: Option<IntMem>) -> Self {
+        let length = tape.len() as IntMem;
+        let mut analysis = Analysis::default();
+        let mut queue = vec![0];
+        let mut guesses = BTreeSet::new();
+
+        loop {
+            while let Some(pc) = queue.pop() {
+                if analysis.instructions.contains_key(&pc) || pc < 0 || pc >= length {
+                    continue;
+                }
+
+                let decoded = match Decoded::decode(tape, pc) {
+                    Ok(d) => d,
+                    // A constant which looked like it might be a jump target
+                    // but was not the start of an instruction.
+                    Err(_) if guesses.contains(&pc) => continue,
+                    // Code which can run but isn't valid yet, so the
+                    // program must write it before it gets there.
+                    Err(_) => {
+                        analysis.undecodable = true;
+                        continue;
+                    }
+                };
+
+                let (targets, indirect) = decoded.successors();
+                analysis.indirect |= indirect;
+                queue.extend(targets);
+                analysis.instructions.insert(pc, decoded);
+            }
+
+            // Indirect jumps can go anywhere, so assume that their targets
+            // appear as constants somewhere in the code which can run.
+            if !analysis.indirect {
+                break;
+            }
+            let constants: Vec<IntMem> = analysis
+                .instructions
+                .values()
+                .flat_map(|d| {
+                    (1..d.len() as u32)
+                        .filter(|&p| d.mode(p) == ParameterMode::Immediate)
+                        .map(|p| d.arg(p))
+                        .collect::<Vec<_>>()
+                })
+                .filter(|c| !analysis.instructions.contains_key(c) && !guesses.contains(c))
+                .collect();
+
+            if constants.is_empty() {
+                break;
+            }
+            guesses.extend(constants.iter().copied());
+            queue.extend(constants);
+        }
+
+        analysis.find_data(length, stack_base);
+        analysis
+    }
+
+    // Freeze every cell which is read or written as data, so that
+    // instructions stored there are left as they are.
+    fn find_data(&mut self, length: IntMem, stack_base: Option<IntMem>) {
+        let mut frozen = BTreeSet::new();
+        let mut written = BTreeSet::new();
+
+        for d in self.instructions.values() {
+            let (reads, write) = d.parameters();
+            for (parameter, is_write) in reads
+                .into_iter()
+                .map(|p| (p, false))
+                .chain(write.map(|p| (p, true)))
+            {
+                match d.mode(parameter) {
+                    ParameterMode::Immediate => {}
+                    ParameterMode::Position => {
+                        frozen.insert(d.arg(parameter));
+                        if is_write {
+                            written.insert(d.arg(parameter));
+                        }
+                    }
+                    ParameterMode::Relative => {
+                        self.relative = true;
+                        let start = stack_base.unwrap_or(0).max(0);
+                        frozen.extend(start..length);
+                        if is_write {
+                            written.extend(start..length);
+                        }
+                    }
+                }
+            }
+        }
+
+        // A store whose destination operand is itself overwritten could
+        // write anywhere, and a load through one could read anywhere.
+        let mut redirected_write = false;
+        let mut redirected_read = false;
+        for d in self.instructions.values() {
+            let (reads, write) = d.parameters();
+            let redirected = |p: &u32| {
+                d.mode(*p) == ParameterMode::Position && written.contains(&(d.pc + *p as IntMem))
+            };
+            redirected_write |= write.iter().any(redirected);
+            redirected_read |= reads.iter().any(redirected);
+        }
+        if redirected_write {
+            written.extend(0..length);
+        }
+        if redirected_write || redirected_read {
+            frozen.extend(0..length);
+        }
+
+        // Instructions which overlap are both code and data.
+        let mut previous: Option<&Decoded> = None;
+        for d in self.instructions.values() {
+            if let Some(p) = previous {
+                if p.cells().end > d.pc {
+                    self.overlapping = true;
+                    frozen.extend(p.cells());
+                    frozen.extend(d.cells());
+                }
+            }
+            previous = Some(d);
+        }
+
+        self.frozen = frozen;
+        self.written = written;
+    }
+
+    fn is_touchable(&self, d: &Decoded) -> bool {
+        d.cells().all(|c| !self.frozen.contains(&c))
+    }
+
+    /// Whether the program writes over an instruction which decides where
+    /// execution goes next, in which case control flow can't be trusted.
+    fn rewrites_control_flow(&self) -> bool {
+        self.undecodable
+            || self.instructions.values().any(|d| {
+                self.written.contains(&d.pc)
+                    || (d.is_jump() && d.cells().any(|c| self.written.contains(&c)))
+            })
+    }
+
+    fn rewrites_code(&self) -> bool {
+        self.instructions
+            .values()
+            .any(|d| d.cells().any(|c| self.written.contains(&c)))
+    }
+}
+
+/// Summary of the changes made by the optimizer.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct OptimizationReport {
+    /// Number of arithmetic and comparison instructions folded into constants.
+    pub folded: usize,
+
+    /// Number of conditional jumps made unconditional or removed.
+    pub jumps: usize,
+
+    /// Number of memory cells removed from the program.
+    pub removed: usize,
+
+    /// Whether addresses were renumbered after removing code.
+    pub renumbered: bool,
+
+    /// Set when the program might rewrite its own control flow, so no changes were made.
+    pub self_modifying: bool,
+}
+
+impl fmt::Display for OptimizationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.self_modifying {
+            return write!(
+                f,
+                "Program may modify its own control flow, no changes made"
+            );
+        }
+        write!(
+            f,
+            "Folded {} constants, simplified {} jumps, removed {} cells",
+            self.folded, self.jumps, self.removed
+        )?;
+        if self.renumbered {
+            write!(f, " (renumbered)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Produces smaller, equivalent versions of Intcode programs.
+#[derive(Debug, Clone, Default)]
+pub struct Optimizer {
+    stack_base: Option<IntMem>,
+}
+
+impl Optimizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Promise that relative mode only ever accesses addresses at or above
+    /// this one. Without it, relative mode is assumed to reach anywhere, and
+    /// programs which use it are left unchanged.
+    pub fn assume_stack_above(mut self, address: IntMem) -> Self {
+        self.stack_base = Some(address);
+        self
+    }
+
+    pub fn optimize(&self, program: &Program) -> Result<(Program, OptimizationReport)> {
+        let mut tape = program.tape();
+        let mut report = OptimizationReport::default();
+
+        let analysis = Analysis::new(&tape, self.stack_base);
+        if analysis.rewrites_control_flow() {
+            report.self_modifying = true;
+            return Ok((program.clone(), report));
+        }
+
+        let mut dead = BTreeSet::new();
+        for d in analysis.instructions.values() {
+            if !analysis.is_touchable(d) {
+                continue;
+            }
+            let before = d.encode();
+
+            let after = match (d.op(), d.taken()) {
+                (Op::JumpIfTrue, Some(true)) | (Op::JumpIfFalse, Some(true)) => {
+                    let word = 105 + 1000 * mode_digit(d.mode(2));
+                    vec![word, 1, d.arg(2)]
+                }
+                (Op::JumpIfTrue, Some(false)) | (Op::JumpIfFalse, Some(false)) => {
+                    dead.insert(d.pc);
+                    continue;
+                }
+                _ => match fold(d) {
+                    Some(v) => vec![1101 + 10000 * mode_digit(d.mode(3)), v, 0, d.arg(3)],
+                    None => continue,
+                },
+            };
+
+            if before != after {
+                if d.is_jump() {
+                    report.jumps += 1;
+                } else {
+                    report.folded += 1;
+                }
+                for (i, word) in after.into_iter().enumerate() {
+                    tape[(d.pc as usize) + i] = word;
+                }
+            }
+        }
+
+        let renumber = !analysis.indirect
+            && !analysis.overlapping
+            && !analysis.rewrites_code()
+            && (!analysis.relative
+                || self
+                    .stack_base
+                    .map(|b| b >= tape.len() as IntMem)
+                    .unwrap_or(false));
+
+        if renumber {
+            let (renumbered, removed) = compact(&tape, &analysis, &dead)?;
+            report.jumps += dead.len();
+            report.removed = removed;
+            report.renumbered = removed > 0;
+            tape = renumbered;
+        }
+
+        Ok((tape.into(), report))
+    }
+}
+
+fn fold(d: &Decoded) -> Option<IntMem> {
+    if d.mode(1) != ParameterMode::Immediate || d.mode(2) != ParameterMode::Immediate {
+        return None;
+    }
+    let (left, right) = (d.arg(1), d.arg(2));
+    match d.op() {
+        Op::Add => left.checked_add(right),
+        Op::Mul => left.checked_mul(right),
+        Op::LessThan => Some((left < right) as IntMem),
+        Op::EqualTo => Some((left == right) as IntMem),
+        _ => None,
+    }
+}
+
+// Remove every cell which is neither code that can run nor data which
+// is used, and update addresses to match. Returns the new tape and the
+// number of cells removed.
+fn compact(
+    tape: &[IntMem],
+    analysis: &Analysis,
+    dead: &BTreeSet<IntMem>,
+) -> Result<(Vec<IntMem>, usize)> {
+    let length = tape.len() as IntMem;
+    let mut keep = vec![false; tape.len()];
+
+    for d in analysis.instructions.values() {
+        if !dead.contains(&d.pc) {
+            for c in d.cells() {
+                keep[c as usize] = true;
+            }
+        }
+    }
+    for &c in analysis.frozen.iter() {
+        if c >= 0 && c < length {
+            keep[c as usize] = true;
+        }
+    }
+
+    // Removed cells map to the next cell which is kept, so that
+    // jumps to a removed instruction carry on from where it was.
+    let mut addresses = vec![0; tape.len() + 1];
+    let mut next = keep.iter().filter(|&&k| k).count() as IntMem;
+    addresses[tape.len()] = next;
+    for i in (0..tape.len()).rev() {
+        if keep[i] {
+            next -= 1;
+        }
+        addresses[i] = next;
+    }
+    let relocate = |a: IntMem| {
+        if a >= 0 && a < length {
+            addresses[a as usize]
+        } else {
+            a
+        }
+    };
+
+    let mut output = Vec::with_capacity(tape.len());
+    let mut pc = 0;
+    while pc < length {
+        if !keep[pc as usize] {
+            pc += 1;
+            continue;
+        }
+        match analysis.instructions.get(&pc) {
+            Some(_) => {
+                let mut d = Decoded::decode(tape, pc)?;
+                for parameter in 1..(d.len() as u32) {
+                    let is_target = d.is_jump() && parameter == 2;
+                    let address = match d.mode(parameter) {
+                        ParameterMode::Position => true,
+                        ParameterMode::Immediate => is_target,
+                        ParameterMode::Relative => false,
+                    };
+                    if address {
+                        d.args[(parameter - 1) as usize] = relocate(d.arg(parameter));
+                    }
+                }
+                output.extend(d.encode());
+                pc += d.len();
+            }
+            None => {
+                output.push(tape[pc as usize]);
+                pc += 1;
+            }
+        }
+    }
+
+    let removed = tape.len() - output.len();
+    Ok((output, removed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Computer;
+
+    fn outputs(program: &Program, input: IntMem) -> Vec<IntMem> {
+        let mut cpu = Computer::new(program.clone());
+        let mut output = Vec::new();
+        loop {
+            match cpu.op().unwrap() {
+                crate::CPUState::Continue => {}
+                crate::CPUState::Output(v) => output.push(v),
+                crate::CPUState::Input => cpu.feed(input).unwrap(),
+                crate::CPUState::Halt => break,
+            }
+        }
+        output
+    }
+
+    #[test]
+    fn fold_constants() {
+        let program: Program = vec![1102, 6, 7, 9, 4, 9, 99, 0, 0, 0].into();
+        let (optimized, report) = Optimizer::new().optimize(&program).unwrap();
+
+        assert_eq!(report.folded, 1);
+        assert_eq!(outputs(&optimized, 0), vec![42]);
+        assert_eq!(optimized.get(1), Some(42));
+    }
+
+    #[test]
+    fn remove_unreachable() {
+        let program: Program = vec![1105, 1, 7, 104, 1, 1106, 0, 104, 2, 99].into();
+        let (optimized, report) = Optimizer::new().optimize(&program).unwrap();
+
+        assert_eq!(outputs(&optimized, 0), vec![2]);
+        assert!(report.renumbered);
+        assert_eq!(report.removed, 4);
+        assert_eq!(optimized.tape(), vec![1105, 1, 3, 104, 2, 99]);
+    }
+
+    #[test]
+    fn remove_dead_jump() {
+        // Reads an input, skips a jump which is never taken,
+        // then compares against data stored after the code.
+        let program: Program = vec![3, 12, 1106, 1, 0, 8, 12, 13, 12, 4, 12, 99, 0, 5].into();
+        let (optimized, report) = Optimizer::new().optimize(&program).unwrap();
+
+        assert_eq!(report.jumps, 1);
+        assert_eq!(optimized.len(), 11);
+        for input in 4..7 {
+            assert_eq!(outputs(&optimized, input), outputs(&program, input));
+        }
+    }
+
+    #[test]
+    fn self_modifying() {
+        // The first instruction rewrites the operand of the output.
+        let program: Program = vec![1101, 1, 1, 5, 104, 0, 99].into();
+        let (optimized, report) = Optimizer::new().optimize(&program).unwrap();
+
+        assert!(!report.renumbered);
+        assert_eq!(optimized.tape(), vec![1101, 2, 0, 5, 104, 0, 99]);
+        assert_eq!(outputs(&optimized, 0), vec![2]);
+
+        // This one rewrites the condition of a jump.
+        let program: Program = vec![1101, 0, 1, 5, 1105, 0, 9, 104, 1, 99].into();
+        let (optimized, report) = Optimizer::new().optimize(&program).unwrap();
+        assert!(report.self_modifying);
+        assert_eq!(optimized.tape(), program.tape());
+
+        // And this one finishes writing an instruction just before it runs.
+        let program: Program = vec![1001, 6, 1, 6, 104, 7, 98].into();
+        let (optimized, report) = Optimizer::new().optimize(&program).unwrap();
+        assert!(report.self_modifying);
+        assert_eq!(outputs(&optimized, 0), vec![7]);
+    }
+
+    #[test]
+    fn redirected_store() {
+        // The first instruction rewrites where the second stores to, so that
+        // it overwrites an operand of the third instead of address 99.
+        let program: Program =
+            vec![1101, 0, 9, 7, 1101, 7, 0, 99, 1101, 2, 3, 20, 4, 20, 99].into();
+        assert_eq!(outputs(&program, 0), vec![10]);
+
+        let (optimized, report) = Optimizer::new().optimize(&program).unwrap();
+        assert!(report.self_modifying);
+        assert_eq!(outputs(&optimized, 0), vec![10]);
+    }
+
+    #[test]
+    fn relative_mode() {
+        let program: Program = vec![109, 20, 21101, 2, 3, 0, 204, 0, 99].into();
+
+        // Without knowing where the stack is, relative writes could land
+        // anywhere, including on the jumps.
+        let (optimized, report) = Optimizer::new().optimize(&program).unwrap();
+        assert!(report.self_modifying);
+        assert_eq!(optimized.tape(), program.tape());
+
+        let (optimized, report) = Optimizer::new()
+            .assume_stack_above(20)
+            .optimize(&program)
+            .unwrap();
+        assert_eq!(report.folded, 1);
+        assert_eq!(outputs(&optimized, 0), vec![5]);
+    }
+}
@@ -0,0 +1,190 @@
+//! Declarative patches, which set memory in a program before it runs.
+//!
+//! A patch file has one entry per line, as an address and the value to
+//! store there, optionally with the value expected to be replaced:
+//!
+//! ```text
+//! # Insert two quarters
+//! 0: 1 -> 2
+//! ```
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::num::ParseIntError;
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::io::Read;
+
+use crate::IntMem;
+
+#[cfg(feature = "std")]
+type AnyResult<T> = std::result::Result<T, anyhow::Error>;
+
+/// A single change to make to a program.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PatchEntry {
+    pub address: IntMem,
+    pub value: IntMem,
+    pub expected: Option<IntMem>,
+}
+
+impl fmt::Display for PatchEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.expected {
+            Some(e) => write!(f, "{}: {} -> {}", self.address, e, self.value),
+            None => write!(f, "{}: {}", self.address, self.value),
+        }
+    }
+}
+
+/// A set of changes to make to a program, applied with
+/// [Program::apply_patch](crate::Program::apply_patch).
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Patch(Vec<PatchEntry>);
+
+#[derive(Debug)]
+pub enum ParsePatchError {
+    ParseIntError(ParseIntError),
+    InvalidEntry(usize, String),
+}
+
+impl fmt::Display for ParsePatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParsePatchError::ParseIntError(e) => write!(f, "Failed to parse integer {}", e),
+            ParsePatchError::InvalidEntry(line, text) => {
+                write!(f, "Invalid patch entry on line {}: {}", line, text)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParsePatchError {}
+
+impl From<ParseIntError> for ParsePatchError {
+    fn from(e: ParseIntError) -> Self {
+        ParsePatchError::ParseIntError(e)
+    }
+}
+
+impl Patch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn read(mut reader: Box<dyn Read + 'static>) -> AnyResult<Self> {
+        let mut buf = String::new();
+        reader.read_to_string(&mut buf)?;
+        Ok(buf.parse()?)
+    }
+
+    /// Store a value at this address, whatever was there before.
+    pub fn set(mut self, address: IntMem, value: IntMem) -> Self {
+        self.0.push(PatchEntry {
+            address,
+            value,
+            expected: None,
+        });
+        self
+    }
+
+    /// Store a value at this address, only if it currently holds the expected value.
+    pub fn replace(mut self, address: IntMem, expected: IntMem, value: IntMem) -> Self {
+        self.0.push(PatchEntry {
+            address,
+            value,
+            expected: Some(expected),
+        });
+        self
+    }
+
+    pub fn entries(&self) -> &[PatchEntry] {
+        &self.0
+    }
+}
+
+impl FromStr for Patch {
+    type Err = ParsePatchError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut patch = Patch::new();
+
+        for (n, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut parts = line.splitn(2, ':');
+            let (address, change) = match (parts.next(), parts.next()) {
+                (Some(a), Some(c)) => (a.trim().parse::<IntMem>()?, c),
+                _ => return Err(ParsePatchError::InvalidEntry(n + 1, line.to_string())),
+            };
+
+            let mut values = change.splitn(2, "->");
+            patch = match (values.next(), values.next()) {
+                (Some(v), None) => patch.set(address, v.trim().parse()?),
+                (Some(e), Some(v)) => patch.replace(address, e.trim().parse()?, v.trim().parse()?),
+                _ => return Err(ParsePatchError::InvalidEntry(n + 1, line.to_string())),
+            };
+        }
+
+        Ok(patch)
+    }
+}
+
+impl fmt::Display for Patch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in self.0.iter() {
+            writeln!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Arguments, IntcodeError, Program};
+
+    #[test]
+    fn parse_patch() {
+        let patch: Patch = "# Day 2\n1: 12\n2: 2 # verb\n\n0: 1 -> 2\n"
+            .parse()
+            .unwrap();
+
+        assert_eq!(patch, Patch::new().set(1, 12).set(2, 2).replace(0, 1, 2));
+        assert_eq!(format!("{}", patch), "1: 12\n2: 2\n0: 1 -> 2\n");
+        assert!("1 12".parse::<Patch>().is_err());
+    }
+
+    #[test]
+    fn apply_patch() {
+        let mut program: Program = vec![1, 0, 0, 0, 99].into();
+        program
+            .apply_patch(&Patch::new().set(1, 4).replace(0, 1, 2))
+            .unwrap();
+        assert_eq!(program.tape(), vec![2, 4, 0, 0, 99]);
+    }
+
+    #[test]
+    fn apply_patch_mismatch() {
+        let mut program: Program = vec![1, 0, 0, 0, 99].into();
+        let patch = Patch::new().set(1, 4).replace(0, 3, 2);
+
+        match program.apply_patch(&patch) {
+            Err(IntcodeError::PatchMismatch {
+                address: 0,
+                expected: 3,
+                found: 1,
+            }) => {}
+            r => panic!("Unexpected result {:?}", r),
+        }
+
+        // Nothing is changed when any expectation fails.
+        assert_eq!(program.tape(), vec![1, 0, 0, 0, 99]);
+    }
+}
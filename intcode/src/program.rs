@@ -1,26 +1,36 @@
-use anyhow::Error;
-use std::collections::HashMap;
-use std::convert::TryInto;
-use std::fmt;
+use alloc::vec::Vec;
+use core::fmt;
+use core::num::ParseIntError;
+use core::str::FromStr;
+#[cfg(feature = "std")]
 use std::io::{BufRead, BufReader, Read};
-use std::str::FromStr;
-use thiserror::Error;
 
+use crate::device::Mapping;
 pub use crate::errors::{IntcodeError, Result};
 use crate::opcode::{OpCode, ParameterMode};
+use crate::patch::Patch;
+use crate::segment::Segment;
 use crate::IntMem;
 
-type AnyResult<T> = std::result::Result<T, Error>;
+#[cfg(feature = "std")]
+type AnyResult<T> = std::result::Result<T, anyhow::Error>;
+
+// Hashing needs a source of randomness from std, so fall back to
+// an ordered map without it.
+#[cfg(feature = "std")]
+type Cells = std::collections::HashMap<IntMem, IntMem>;
+#[cfg(not(feature = "std"))]
+type Cells = alloc::collections::BTreeMap<IntMem, IntMem>;
 
 pub trait Arguments: Sized {
     fn argument(&self, address: IntMem) -> Result<IntMem>;
     fn tape(&self) -> Vec<IntMem>;
     fn len(&self) -> IntMem;
 
-    fn instruction(&self, address: IntMem) -> AnyResult<Instruction> {
+    fn instruction(&self, address: IntMem) -> Result<Instruction> {
         let op = OpCode::new(self.argument(address)?)?;
         let n = op.n_arguments();
-        let mut args = Vec::with_capacity((n - 1).try_into()?);
+        let mut args = Vec::with_capacity((n - 1) as usize);
 
         for i in (1 as IntMem)..(n as IntMem) {
             args.push(self.argument(address + i)?);
@@ -37,18 +47,34 @@ pub trait Arguments: Sized {
 }
 
 #[derive(Debug, Clone)]
-pub struct Program(HashMap<IntMem, IntMem>);
+pub struct Program(Cells);
 
-#[derive(Debug, Error)]
+#[derive(Debug)]
 pub enum ParseProgramError {
-    #[error("Failed to parse integer {0}")]
-    ParseIntError(#[from] std::num::ParseIntError),
+    ParseIntError(ParseIntError),
+}
+
+impl fmt::Display for ParseProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseProgramError::ParseIntError(e) => write!(f, "Failed to parse integer {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseProgramError {}
+
+impl From<ParseIntError> for ParseProgramError {
+    fn from(e: ParseIntError) -> Self {
+        ParseProgramError::ParseIntError(e)
+    }
 }
 
 impl FromStr for Program {
     type Err = ParseProgramError;
 
-    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
         let mut v = Vec::new();
         for line in s.lines() {
             let elements = line
@@ -57,7 +83,7 @@ impl FromStr for Program {
                 .split(",")
                 .map(|element| element.trim())
                 .map(|element| element.parse::<IntMem>())
-                .collect::<std::result::Result<Vec<IntMem>, std::num::ParseIntError>>()?;
+                .collect::<core::result::Result<Vec<IntMem>, ParseIntError>>()?;
             v.extend(elements)
         }
         Ok(v.into())
@@ -65,6 +91,7 @@ impl FromStr for Program {
 }
 
 impl Program {
+    #[cfg(feature = "std")]
     pub fn read(reader: Box<dyn Read + 'static>) -> AnyResult<Self> {
         let bufread = BufReader::new(reader);
         let mut v = Vec::new();
@@ -74,7 +101,7 @@ impl Program {
                 .split(",")
                 .map(|element| element.trim())
                 .map(|element| element.parse::<IntMem>())
-                .collect::<std::result::Result<Vec<IntMem>, std::num::ParseIntError>>()?;
+                .collect::<core::result::Result<Vec<IntMem>, ParseIntError>>()?;
             v.extend(elements)
         }
         Ok(v.into())
@@ -88,6 +115,33 @@ impl Program {
         self.0.insert(address, value);
         Ok(())
     }
+
+    /// Apply each entry in a patch. When any expected value does not
+    /// match, the program is left unchanged.
+    pub fn apply_patch(&mut self, patch: &Patch) -> Result<()> {
+        for entry in patch.entries() {
+            let found = self.get(entry.address).unwrap_or(0);
+            match entry.expected {
+                Some(expected) if expected != found => {
+                    return Err(IntcodeError::PatchMismatch {
+                        address: entry.address,
+                        expected,
+                        found,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        for entry in patch.entries() {
+            self.insert(entry.address, entry.value)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn remove(&mut self, address: IntMem) -> Option<IntMem> {
+        self.0.remove(&address)
+    }
 }
 
 impl Arguments for Program {
@@ -122,10 +176,28 @@ impl Into<Program> for Vec<IntMem> {
     }
 }
 
-#[derive(Debug, Clone)]
+/// The memory of a running program.
+///
+/// Cloning copies the program, stack pointer and segments, but not any
+/// attached devices: they are host peripherals which can't be duplicated,
+/// so attach them to the clone again if it needs them.
+#[derive(Debug)]
 pub struct Memory {
     stack_pointer: IntMem,
     registers: Program,
+    segments: Vec<Segment>,
+    devices: Vec<Mapping>,
+}
+
+impl Clone for Memory {
+    fn clone(&self) -> Self {
+        Self {
+            stack_pointer: self.stack_pointer,
+            registers: self.registers.clone(),
+            segments: self.segments.clone(),
+            devices: Vec::new(),
+        }
+    }
 }
 
 impl Memory {
@@ -133,6 +205,65 @@ impl Memory {
         Self {
             stack_pointer: 0,
             registers: program,
+            segments: Vec::new(),
+            devices: Vec::new(),
+        }
+    }
+
+    // Devices are checked in the order they were attached, and shadow
+    // both program memory and segments in the range they cover.
+    pub(crate) fn attach(&mut self, mapping: Mapping) {
+        self.devices.push(mapping);
+    }
+
+    fn device(&mut self, address: IntMem) -> Option<&mut Mapping> {
+        self.devices.iter_mut().find(|d| d.contains(address))
+    }
+
+    // Segments are only enforced once at least one has been set up,
+    // otherwise all of memory can be read and written.
+    pub fn set_segments(&mut self, segments: Vec<Segment>) {
+        self.segments = segments;
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    fn segment(&self, address: IntMem) -> Option<&Segment> {
+        self.segments.iter().find(|s| s.contains(address))
+    }
+
+    fn check_read(&self, pc: IntMem, address: IntMem) -> Result<()> {
+        if self.segments.is_empty() {
+            return Ok(());
+        }
+
+        match self.segment(address) {
+            Some(s) if !s.permissions().read => Err(IntcodeError::ReadProtected {
+                pc,
+                address,
+                segment: s.kind(),
+            }),
+            Some(_) => Ok(()),
+            None if self.registers.get(address).is_some() => Ok(()),
+            None => Err(IntcodeError::SegmentationFault { pc, address }),
+        }
+    }
+
+    fn check_write(&self, pc: IntMem, address: IntMem) -> Result<()> {
+        if self.segments.is_empty() {
+            return Ok(());
+        }
+
+        match self.segment(address) {
+            Some(s) if !s.permissions().write => Err(IntcodeError::WriteProtected {
+                pc,
+                address,
+                segment: s.kind(),
+            }),
+            Some(_) => Ok(()),
+            None => Err(IntcodeError::SegmentationFault { pc, address }),
         }
     }
 
@@ -149,38 +280,84 @@ impl Memory {
         Ok(())
     }
 
+    pub fn stack_pointer(&self) -> IntMem {
+        self.stack_pointer
+    }
+
+    pub fn get(&self, address: IntMem) -> Option<IntMem> {
+        self.registers.get(address)
+    }
+
+    // Put memory back the way it was before a write, used when stepping
+    // backwards through execution history. A value of None means the
+    // address had never been written.
+    pub(crate) fn restore(&mut self, address: IntMem, value: Option<IntMem>) {
+        match value {
+            Some(v) => {
+                self.registers.0.insert(address, v);
+            }
+            None => {
+                self.registers.remove(address);
+            }
+        }
+    }
+
+    pub(crate) fn set_stack_pointer(&mut self, value: IntMem) {
+        self.stack_pointer = value;
+    }
+
     // Load a memory value from an address, with a parameter mode. Parameter modes
     // allow memory to be loaded immediately (as the literal value), by position
     // (where addresses must be positive integers), or relatively (relative to
-    // the stack pointer).
-    pub fn load(&mut self, address: IntMem, mode: ParameterMode) -> Result<IntMem> {
+    // the stack pointer). The program counter is only used to report
+    // segmentation faults.
+    pub fn load(&mut self, pc: IntMem, address: IntMem, mode: ParameterMode) -> Result<IntMem> {
         let target = self.registers.argument(address)?;
 
-        match (mode, target) {
-            (ParameterMode::Immediate, t) => Ok(t),
-            (ParameterMode::Position, a) if a < 0 => Err(IntcodeError::InvalidAddress(a)),
-            (ParameterMode::Position, a) => Ok(self.registers.get(a).unwrap_or(0)),
-            (ParameterMode::Relative, r) => {
-                Ok(self.registers.get(r + self.stack_pointer).unwrap_or(0))
-            }
+        let location = match (mode, target) {
+            (ParameterMode::Immediate, t) => return Ok(t),
+            (ParameterMode::Position, a) if a < 0 => return Err(IntcodeError::InvalidAddress(a)),
+            (ParameterMode::Position, a) => a,
+            (ParameterMode::Relative, r) => r + self.stack_pointer,
+        };
+
+        if let Some(device) = self.device(location) {
+            return Ok(device.load(location));
         }
+
+        self.check_read(pc, location)?;
+        Ok(self.registers.get(location).unwrap_or(0))
     }
 
-    pub fn save(&mut self, address: IntMem, mode: ParameterMode, value: IntMem) -> Result<()> {
+    // Find the address which a save with this parameter mode would write to.
+    pub fn target(&self, address: IntMem, mode: ParameterMode) -> Result<IntMem> {
         let target = self.registers.argument(address)?;
 
         match (mode, target) {
             (ParameterMode::Immediate, _) => Err(IntcodeError::IllegalParameterMode(mode)),
             (ParameterMode::Position, a) if a < 0 => Err(IntcodeError::InvalidAddress(a)),
-            (ParameterMode::Position, a) => {
-                self.registers.insert(a, value)?;
-                Ok(())
-            }
-            (ParameterMode::Relative, r) => {
-                self.registers.insert(r + self.stack_pointer, value)?;
-                Ok(())
-            }
+            (ParameterMode::Position, a) => Ok(a),
+            (ParameterMode::Relative, r) => Ok(r + self.stack_pointer),
+        }
+    }
+
+    pub fn save(
+        &mut self,
+        pc: IntMem,
+        address: IntMem,
+        mode: ParameterMode,
+        value: IntMem,
+    ) -> Result<()> {
+        let target = self.target(address, mode)?;
+
+        if let Some(device) = self.device(target) {
+            device.save(target, value);
+            return Ok(());
         }
+
+        self.check_write(pc, target)?;
+        self.registers.insert(target, value)?;
+        Ok(())
     }
 }
 
@@ -0,0 +1,230 @@
+use anyhow::{anyhow, Error};
+use clap::{App, Arg};
+use intcode::{CPUState, Computer, Condition, IntMem, Patch, Program, Scanner};
+use std::fs::File;
+use std::io::{self, BufRead, Write};
+
+type Result<T> = std::result::Result<T, Error>;
+
+const HELP: &str = "\
+run [INPUT]              give the program INPUT (default 0), then run until it asks for more
+changed | unchanged      keep addresses which did or didn't change since the last scan
+increased | decreased    keep addresses which went up or down since the last scan
+equals VALUE             keep addresses which now hold VALUE
+by DELTA                 keep addresses which changed by DELTA
+correlates VALUE         keep addresses which stay a fixed distance from VALUE
+tracks FIELD INDEX=VALUE correlate with FIELD of the last output triple where INDEX is VALUE
+list                     show the remaining addresses and their values
+reset                    make every address a candidate again, e.g. to track something else
+quit                     exit";
+
+/// Number of candidates to show before eliding the rest.
+const LIST_LIMIT: usize = 20;
+
+fn program(filename: &str) -> Result<Program> {
+    let f: File = File::open(filename)?;
+    Program::read(Box::new(f))
+}
+
+fn patch(filename: &str) -> Result<Patch> {
+    let f: File = File::open(filename)?;
+    Patch::read(Box::new(f))
+}
+
+struct Session {
+    cpu: Computer,
+    scanner: Scanner,
+    outputs: Vec<IntMem>,
+    waiting: bool,
+    halted: bool,
+}
+
+impl Session {
+    fn new(program: Program) -> Self {
+        let cpu = Computer::new(program);
+        let scanner = Scanner::new(cpu.snapshot());
+        Session {
+            cpu,
+            scanner,
+            outputs: Vec::new(),
+            waiting: false,
+            halted: false,
+        }
+    }
+
+    fn run(&mut self, input: Option<IntMem>) -> Result<String> {
+        if self.halted {
+            return Err(anyhow!("The program has halted"));
+        }
+        // Once the program asks for input, it only continues when given some.
+        if let Some(value) = input.or(if self.waiting { Some(0) } else { None }) {
+            self.cpu.feed(value)?;
+        }
+
+        let start = self.outputs.len();
+        loop {
+            match self.cpu.run()? {
+                CPUState::Output(o) => self.outputs.push(o),
+                CPUState::Input => {
+                    self.waiting = true;
+                    break;
+                }
+                CPUState::Halt => {
+                    self.halted = true;
+                    break;
+                }
+                CPUState::Continue => {}
+            }
+        }
+
+        let state = if self.halted { "halted" } else { "waiting" };
+        Ok(format!("{} outputs, {}", self.outputs.len() - start, state))
+    }
+
+    // The requested field of the most recent output triple with a
+    // matching value at the given index.
+    fn triple(&self, field: usize, index: usize, value: IntMem) -> Result<IntMem> {
+        self.outputs
+            .chunks_exact(3)
+            .rev()
+            .find(|t| t[index] == value)
+            .map(|t| t[field])
+            .ok_or_else(|| anyhow!("No output triple has {} at {}", value, index))
+    }
+
+    fn filter(&mut self, condition: Condition) -> String {
+        let n = self.scanner.filter(self.cpu.snapshot(), condition);
+        format!("{} candidates", n)
+    }
+
+    fn list(&self) -> String {
+        let values = self.scanner.values();
+        let mut lines: Vec<String> = values
+            .iter()
+            .take(LIST_LIMIT)
+            .map(|(a, v)| format!("{:04}: {}", a, v))
+            .collect();
+        if values.len() > LIST_LIMIT {
+            lines.push(format!("... {} more", values.len() - LIST_LIMIT));
+        }
+        lines.join("\n")
+    }
+
+    fn command(&mut self, line: &str) -> Result<Option<String>> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let number = |i: usize| -> Result<IntMem> {
+            let word = words
+                .get(i)
+                .ok_or_else(|| anyhow!("Missing argument to {}", words[0]))?;
+            Ok(word.parse()?)
+        };
+
+        let response = match words.as_slice() {
+            [] => return Ok(Some(String::new())),
+            ["run"] => self.run(None)?,
+            ["run", _] => self.run(Some(number(1)?))?,
+            ["changed"] => self.filter(Condition::Changed),
+            ["unchanged"] => self.filter(Condition::Unchanged),
+            ["increased"] => self.filter(Condition::Increased),
+            ["decreased"] => self.filter(Condition::Decreased),
+            ["equals", _] => self.filter(Condition::Equals(number(1)?)),
+            ["by", _] => self.filter(Condition::ChangedBy(number(1)?)),
+            ["correlates", _] => self.filter(Condition::Correlates(number(1)?)),
+            ["tracks", field, selector] => {
+                let field: usize = field.parse()?;
+                let (index, value) = selector
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("Expected INDEX=VALUE, got {}", selector))?;
+                let index: usize = index.parse()?;
+                if field > 2 || index > 2 {
+                    return Err(anyhow!("Triples only have fields 0, 1 and 2"));
+                }
+                let value = self.triple(field, index, value.parse()?)?;
+                self.filter(Condition::Correlates(value))
+            }
+            ["list"] => self.list(),
+            ["reset"] => {
+                self.scanner = Scanner::new(self.cpu.snapshot());
+                format!("{} candidates", self.scanner.len())
+            }
+            ["help"] => HELP.to_string(),
+            ["quit"] | ["exit"] => return Ok(None),
+            _ => return Err(anyhow!("Unknown command: {}, try help", line.trim())),
+        };
+        Ok(Some(response))
+    }
+}
+
+fn main() -> Result<()> {
+    let matches = App::new("Advent of Code 2019")
+        .version("1.0")
+        .author("Alex Rudy <opensource@alexrudy.net>")
+        .about("Scan the memory of a running Intcode Program for variables")
+        .after_help(HELP)
+        .arg(
+            Arg::with_name("patch")
+                .short("p")
+                .long("patch")
+                .value_name("PATCH")
+                .help("Apply a patch file to the program before running it")
+                .required(false)
+                .multiple(true)
+                .number_of_values(1)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("program")
+                .value_name("PROGRAM")
+                .required(true)
+                .takes_value(true)
+                .index(1),
+        )
+        .get_matches();
+
+    let mut prog = program(matches.value_of("program").unwrap())?;
+    if let Some(patches) = matches.values_of("patch") {
+        for filename in patches {
+            prog.apply_patch(&patch(filename)?)?;
+        }
+    }
+
+    let mut session = Session::new(prog);
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    write!(stdout, "> ")?;
+    stdout.flush()?;
+    for line in stdin.lock().lines() {
+        match session.command(&line?) {
+            Ok(Some(response)) if response.is_empty() => {}
+            Ok(Some(response)) => writeln!(stdout, "{}", response)?,
+            Ok(None) => break,
+            Err(e) => writeln!(stdout, "error: {}", e)?,
+        }
+        write!(stdout, "> ")?;
+        stdout.flush()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_default_input() {
+        // Outputs the last input, then asks for another.
+        let program = vec![4, 9, 3, 9, 1105, 1, 0, 99, 0, 5];
+        let mut session = Session::new(program.into());
+
+        for _ in 0..3 {
+            let response = session.command("run").unwrap();
+            assert_eq!(response.as_deref(), Some("1 outputs, waiting"));
+        }
+        assert_eq!(session.outputs, vec![5, 0, 0]);
+
+        session.command("run 7").unwrap();
+        assert_eq!(session.outputs.last(), Some(&7));
+    }
+}
@@ -0,0 +1,178 @@
+//! Find the memory addresses which hold a running program's state.
+//!
+//! A [Scanner] starts with every address as a candidate. Each call to
+//! [Scanner::filter] compares a new snapshot of memory against the previous
+//! one and drops the candidates which don't match a [Condition], so that a
+//! handful of scans is usually enough to find a score or a position.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::IntMem;
+
+/// A test applied to each candidate address between two snapshots.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Condition {
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+
+    /// The value is now exactly this.
+    Equals(IntMem),
+
+    /// The value changed by exactly this amount.
+    ChangedBy(IntMem),
+
+    /// The value has kept the same offset from this one at every
+    /// correlation scan, e.g. a coordinate which is also sent as output.
+    /// The first correlation scan only records each offset, so follow one
+    /// value per scanner.
+    Correlates(IntMem),
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Changed => write!(f, "changed"),
+            Condition::Unchanged => write!(f, "unchanged"),
+            Condition::Increased => write!(f, "increased"),
+            Condition::Decreased => write!(f, "decreased"),
+            Condition::Equals(v) => write!(f, "equals {}", v),
+            Condition::ChangedBy(v) => write!(f, "changed by {}", v),
+            Condition::Correlates(v) => write!(f, "correlates with {}", v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Candidate {
+    // Offset from the value given to the most recent correlation scan.
+    offset: Option<IntMem>,
+}
+
+/// Narrows down which addresses could hold a value of interest.
+#[derive(Debug, Clone)]
+pub struct Scanner {
+    previous: Vec<IntMem>,
+    candidates: BTreeMap<IntMem, Candidate>,
+}
+
+// Memory grows as the program writes past the end of its tape, and
+// addresses which have never been written read as zero.
+fn value(snapshot: &[IntMem], address: IntMem) -> IntMem {
+    snapshot.get(address as usize).copied().unwrap_or(0)
+}
+
+impl Scanner {
+    /// Start scanning from a snapshot, with every address as a candidate.
+    pub fn new(snapshot: Vec<IntMem>) -> Self {
+        let candidates = (0..snapshot.len() as IntMem)
+            .map(|a| (a, Candidate::default()))
+            .collect();
+        Scanner {
+            previous: snapshot,
+            candidates,
+        }
+    }
+
+    /// Only keep candidates which match the condition between the previous
+    /// snapshot and this one, returning how many remain.
+    pub fn filter(&mut self, snapshot: Vec<IntMem>, condition: Condition) -> usize {
+        let previous = &self.previous;
+        self.candidates.retain(|&address, candidate| {
+            let before = value(previous, address);
+            let after = value(&snapshot, address);
+            match condition {
+                Condition::Changed => after != before,
+                Condition::Unchanged => after == before,
+                Condition::Increased => after > before,
+                Condition::Decreased => after < before,
+                Condition::Equals(v) => after == v,
+                Condition::ChangedBy(d) => after - before == d,
+                Condition::Correlates(v) => {
+                    let offset = after - v;
+                    candidate.offset.replace(offset).unwrap_or(offset) == offset
+                }
+            }
+        });
+        self.previous = snapshot;
+        self.candidates.len()
+    }
+
+    /// Remaining candidate addresses, in order.
+    pub fn candidates(&self) -> Vec<IntMem> {
+        self.candidates.keys().copied().collect()
+    }
+
+    /// The value of each remaining candidate in the most recent snapshot.
+    pub fn values(&self) -> Vec<(IntMem, IntMem)> {
+        self.candidates
+            .keys()
+            .map(|&a| (a, value(&self.previous, a)))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CPUState, Computer};
+
+    #[test]
+    fn conditions() {
+        let mut scanner = Scanner::new(vec![1, 2, 3, 4, 5]);
+        assert_eq!(scanner.filter(vec![1, 3, 3, 2, 5], Condition::Changed), 2);
+        assert_eq!(scanner.candidates(), vec![1, 3]);
+
+        assert_eq!(scanner.filter(vec![1, 4, 3, 1, 5], Condition::Decreased), 1);
+        assert_eq!(scanner.values(), vec![(3, 1)]);
+
+        let mut scanner = Scanner::new(vec![1, 2, 3]);
+        scanner.filter(vec![3, 4, 3, 7], Condition::ChangedBy(2));
+        assert_eq!(scanner.candidates(), vec![0, 1]);
+        scanner.filter(vec![3, 4, 3, 7], Condition::Equals(4));
+        assert_eq!(scanner.candidates(), vec![1]);
+    }
+
+    #[test]
+    fn correlates() {
+        let mut scanner = Scanner::new(vec![0, 0, 0]);
+        scanner.filter(vec![5, 6, 9], Condition::Correlates(5));
+        scanner.filter(vec![7, 8, 9], Condition::Correlates(7));
+        assert_eq!(scanner.candidates(), vec![0, 1]);
+        scanner.filter(vec![2, 4, 9], Condition::Correlates(2));
+        assert_eq!(scanner.candidates(), vec![0]);
+    }
+
+    #[test]
+    fn find_counter() {
+        // Counts the inputs it has been given at address 14, and outputs
+        // the count after each one.
+        let program = vec![3, 13, 1001, 14, 1, 14, 4, 14, 1105, 1, 0, 99, 0, 0, 0];
+        let mut cpu = Computer::new(program);
+        assert_eq!(cpu.run().unwrap(), CPUState::Input);
+        let mut scanner = Scanner::new(cpu.snapshot());
+
+        for value in [7, 7, 3].iter() {
+            cpu.feed(*value).unwrap();
+            let count = match cpu.run().unwrap() {
+                CPUState::Output(o) => o,
+                s => panic!("Unexpected state {:?}", s),
+            };
+            assert_eq!(cpu.run().unwrap(), CPUState::Input);
+            scanner.filter(cpu.snapshot(), Condition::Correlates(count));
+        }
+
+        assert_eq!(scanner.candidates(), vec![14]);
+    }
+}
@@ -0,0 +1,179 @@
+//! Memory segmentation, to describe and enforce how Intcode memory is laid out.
+
+use core::fmt;
+use core::ops::Range;
+
+use crate::IntMem;
+
+/// The role a region of memory plays in a program.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SegmentKind {
+    Code,
+    Data,
+    Stack,
+}
+
+impl fmt::Display for SegmentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SegmentKind::Code => write!(f, "code"),
+            SegmentKind::Data => write!(f, "data"),
+            SegmentKind::Stack => write!(f, "stack"),
+        }
+    }
+}
+
+/// What a program may do with the memory in a segment.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Permissions {
+    pub read: bool,
+    pub write: bool,
+}
+
+impl Permissions {
+    pub fn read_only() -> Self {
+        Permissions {
+            read: true,
+            write: false,
+        }
+    }
+
+    pub fn read_write() -> Self {
+        Permissions {
+            read: true,
+            write: true,
+        }
+    }
+}
+
+impl fmt::Display for Permissions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            if self.read { "r" } else { "-" },
+            if self.write { "w" } else { "-" }
+        )
+    }
+}
+
+/// A contiguous region of memory with its own permissions.
+///
+/// Code segments are read-only by default, while data and
+/// stack segments can be read and written.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Segment {
+    kind: SegmentKind,
+    range: Range<IntMem>,
+    permissions: Permissions,
+}
+
+impl Segment {
+    pub fn new(kind: SegmentKind, range: Range<IntMem>) -> Self {
+        let permissions = match kind {
+            SegmentKind::Code => Permissions::read_only(),
+            SegmentKind::Data | SegmentKind::Stack => Permissions::read_write(),
+        };
+
+        Segment {
+            kind,
+            range,
+            permissions,
+        }
+    }
+
+    pub fn code(range: Range<IntMem>) -> Self {
+        Segment::new(SegmentKind::Code, range)
+    }
+
+    pub fn data(range: Range<IntMem>) -> Self {
+        Segment::new(SegmentKind::Data, range)
+    }
+
+    pub fn stack(range: Range<IntMem>) -> Self {
+        Segment::new(SegmentKind::Stack, range)
+    }
+
+    pub fn with_permissions(mut self, permissions: Permissions) -> Self {
+        self.permissions = permissions;
+        self
+    }
+
+    pub fn kind(&self) -> SegmentKind {
+        self.kind
+    }
+
+    pub fn range(&self) -> &Range<IntMem> {
+        &self.range
+    }
+
+    pub fn permissions(&self) -> Permissions {
+        self.permissions
+    }
+
+    pub fn contains(&self, address: IntMem) -> bool {
+        self.range.contains(&address)
+    }
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{:04}..{:04}) {} {}",
+            self.range.start, self.range.end, self.permissions, self.kind
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Computer, IntcodeError};
+
+    #[test]
+    fn display_segment() {
+        assert_eq!(format!("{}", Segment::code(0..12)), "[0000..0012) r- code");
+        assert_eq!(
+            format!("{}", Segment::stack(100..200)),
+            "[0100..0200) rw stack"
+        );
+    }
+
+    #[test]
+    fn write_to_code() {
+        let mut cpu =
+            Computer::new(vec![1101, 1, 2, 0, 99]).with_segments(vec![Segment::code(0..5)]);
+
+        match cpu.run().unwrap_err().root() {
+            IntcodeError::WriteProtected {
+                pc: 0,
+                address: 0,
+                segment: SegmentKind::Code,
+            } => {}
+            e => panic!("Unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn read_outside_segments() {
+        let program = vec![1001, 10, 1, 5, 99, 0];
+        let segments = vec![Segment::code(0..5), Segment::data(5..6)];
+        let mut cpu = Computer::new(program).with_segments(segments);
+
+        match cpu.run().unwrap_err().root() {
+            IntcodeError::SegmentationFault { pc: 0, address: 10 } => {}
+            e => panic!("Unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn stack_segment() {
+        let program = vec![109, 10, 21101, 1, 2, 0, 99];
+        let segments = vec![Segment::code(0..7), Segment::stack(10..20)];
+        let mut cpu = Computer::new(program).with_segments(segments);
+
+        cpu.run().unwrap();
+        assert_eq!(cpu.program().get(10), Some(3));
+    }
+}
@@ -0,0 +1,116 @@
+//! A short record of recently executed instructions, kept so that
+//! runtime errors can show how the program got where it did.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::opcode::OpCode;
+use crate::IntMem;
+
+/// Number of instructions remembered by default.
+pub(crate) const DEFAULT_TRACE_LENGTH: usize = 16;
+
+/// An instruction which ran to completion.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Executed {
+    pub pc: IntMem,
+    pub instruction: IntMem,
+}
+
+impl fmt::Display for Executed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}: {}", self.pc, self.instruction)?;
+        if let Ok(opcode) = OpCode::new(self.instruction) {
+            write!(f, " ({})", opcode.op())?;
+        }
+        Ok(())
+    }
+}
+
+/// Ring buffer of the most recently executed instructions.
+#[derive(Debug)]
+pub(crate) struct Trace {
+    capacity: usize,
+    entries: VecDeque<Executed>,
+}
+
+impl Trace {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Trace {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub(crate) fn push(&mut self, pc: IntMem, instruction: IntMem) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(Executed { pc, instruction });
+    }
+
+    /// Recorded instructions, oldest first.
+    pub(crate) fn entries(&self) -> Vec<Executed> {
+        self.entries.iter().copied().collect()
+    }
+}
+
+impl Default for Trace {
+    fn default() -> Self {
+        Trace::new(DEFAULT_TRACE_LENGTH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Computer, IntcodeError};
+
+    #[test]
+    fn ring_buffer() {
+        let mut trace = Trace::new(2);
+        trace.push(0, 1);
+        trace.push(4, 2);
+        trace.push(8, 99);
+
+        let pcs: Vec<IntMem> = trace.entries().iter().map(|e| e.pc).collect();
+        assert_eq!(pcs, vec![4, 8]);
+    }
+
+    #[test]
+    fn runtime_error_context() {
+        let program = vec![109, 7, 1101, 40, 2, 6, 0, 99];
+        let mut cpu = Computer::new(program);
+
+        let error = match cpu.run() {
+            Err(IntcodeError::Runtime(e)) => e,
+            r => panic!("Unexpected result {:?}", r),
+        };
+
+        assert!(matches!(error.error, IntcodeError::UnknownOpcode(42)));
+        assert_eq!(error.pc, 6);
+        assert_eq!(error.instruction, Some(42));
+        assert_eq!(error.relative_base, 7);
+        assert_eq!(
+            error.recent,
+            vec![
+                Executed {
+                    pc: 0,
+                    instruction: 109
+                },
+                Executed {
+                    pc: 2,
+                    instruction: 1101
+                },
+            ]
+        );
+
+        let report = error.report();
+        assert!(report.starts_with("Unknown opcode: 42\n"));
+        assert!(report.contains("0002: 1101 (Add)"));
+    }
+}
@@ -0,0 +1,64 @@
+use anyhow::Error;
+use clap::{App, Arg};
+use intcode::{transpile, Language, Program};
+use std::fs::File;
+
+type Result<T> = std::result::Result<T, Error>;
+
+fn program(filename: Option<&str>) -> Result<Program> {
+    let reader: Box<dyn ::std::io::Read + 'static> = match filename {
+        Some("-") => Box::new(::std::io::stdin()),
+        Some(path) => {
+            let f: File = File::open(path)?;
+            Box::new(f)
+        }
+        None => Box::new(::std::io::stdin()),
+    };
+
+    Program::read(reader)
+}
+
+fn main() -> Result<()> {
+    let matches = App::new("Advent of Code 2019")
+        .version("1.0")
+        .author("Alex Rudy <opensource@alexrudy.net>")
+        .about("Transpile Intcode Programs to C or Rust")
+        .arg(
+            Arg::with_name("language")
+                .short("l")
+                .long("language")
+                .value_name("LANGUAGE")
+                .help("Language to generate, either c or rust")
+                .required(false)
+                .default_value("c")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("OUTPUT")
+                .help("Write the generated source to this file instead of stdout")
+                .required(false)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("program")
+                .value_name("PROGRAM")
+                .required(false)
+                .takes_value(true)
+                .index(1),
+        )
+        .get_matches();
+
+    let language: Language = matches.value_of("language").unwrap_or("c").parse()?;
+    let prog = program(matches.value_of("program"))?;
+    let source = transpile(&prog, language);
+
+    match matches.value_of("output") {
+        Some(path) => std::fs::write(path, source)?,
+        None => print!("{}", source),
+    }
+
+    Ok(())
+}
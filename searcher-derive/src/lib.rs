@@ -0,0 +1,273 @@
+//! Derive macros for the searcher candidate traits.
+//!
+//! Fields are picked out with a `#[search(...)]` attribute:
+//!
+//! - `#[search(cost)]` marks the field which ranks candidates. Lower costs
+//!   are better, so the generated `Ord` is reversed. Use
+//!   `#[search(cost = "method")]` to rank by a method called on the field
+//!   instead, along with `value = "Type"` naming its return type when
+//!   deriving `SearchCacher`.
+//! - `#[search(state)]` marks the fields which make up the search state.
+//!   With more than one, the state is a tuple in field order.
+//!
+//! ```ignore
+//! #[derive(Debug, Clone, SearchOrd, SearchState, SearchCacher)]
+//! struct Walker {
+//!     #[search(state)]
+//!     position: (i32, i32),
+//!     #[search(cost)]
+//!     steps: usize,
+//! }
+//! ```
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields, Ident, LitStr, Member,
+    Result, Type,
+};
+
+/// Implement `Ord`, `PartialOrd`, `PartialEq` and `Eq` by comparing the
+/// `#[search(cost)]` field, so that the cheapest candidate is the greatest.
+#[proc_macro_derive(SearchOrd, attributes(search))]
+pub fn derive_search_ord(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input, ord)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Implement `SearchState` from the `#[search(state)]` fields.
+#[proc_macro_derive(SearchState, attributes(search))]
+pub fn derive_search_state(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input, state)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Implement `SearchCacher`, using the `#[search(cost)]` field as the value.
+#[proc_macro_derive(SearchCacher, attributes(search))]
+pub fn derive_search_cacher(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input, cacher)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+struct Cost {
+    member: Member,
+    ty: Type,
+    method: Option<Ident>,
+    value: Option<Type>,
+}
+
+impl Cost {
+    // The expression which ranks a candidate, given the candidate itself.
+    fn of(&self, this: TokenStream2) -> TokenStream2 {
+        let member = &self.member;
+        match &self.method {
+            Some(method) => quote!(#this.#member.#method()),
+            None => quote!(#this.#member),
+        }
+    }
+}
+
+struct Annotated {
+    cost: Option<Cost>,
+    state: Vec<(Member, Type)>,
+}
+
+impl Annotated {
+    fn cost(&self, input: &DeriveInput) -> Result<&Cost> {
+        self.cost.as_ref().ok_or_else(|| {
+            Error::new_spanned(&input.ident, "expected a field marked #[search(cost)]")
+        })
+    }
+}
+
+fn annotations(input: &DeriveInput) -> Result<Annotated> {
+    let fields = match &input.data {
+        Data::Struct(s) => &s.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "search traits can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut annotated = Annotated {
+        cost: None,
+        state: Vec::new(),
+    };
+
+    let members: Vec<Member> = match fields {
+        Fields::Named(named) => named
+            .named
+            .iter()
+            .map(|f| Member::Named(f.ident.clone().unwrap()))
+            .collect(),
+        _ => (0..fields.len()).map(Member::from).collect(),
+    };
+
+    for (field, member) in fields.iter().zip(members) {
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("search")) {
+            let mut cost = None;
+            let mut value = None;
+            let mut is_state = false;
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("cost") {
+                    let method = if meta.input.peek(syn::Token![=]) {
+                        let name: LitStr = meta.value()?.parse()?;
+                        Some(format_ident!("{}", name.value(), span = name.span()))
+                    } else {
+                        None
+                    };
+                    cost = Some(method);
+                    Ok(())
+                } else if meta.path.is_ident("value") {
+                    let name: LitStr = meta.value()?.parse()?;
+                    value = Some(name.parse::<Type>()?);
+                    Ok(())
+                } else if meta.path.is_ident("state") {
+                    is_state = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected cost, value or state"))
+                }
+            })?;
+
+            if let Some(method) = cost {
+                if annotated.cost.is_some() {
+                    return Err(Error::new_spanned(attr, "only one field can be the cost"));
+                }
+                annotated.cost = Some(Cost {
+                    member: member.clone(),
+                    ty: field.ty.clone(),
+                    method,
+                    value,
+                });
+            } else if value.is_some() {
+                return Err(Error::new_spanned(attr, "value is only used with cost"));
+            }
+
+            if is_state {
+                annotated.state.push((member.clone(), field.ty.clone()));
+            }
+        }
+    }
+
+    Ok(annotated)
+}
+
+fn expand<F>(mut input: DeriveInput, generate: F) -> Result<TokenStream2>
+where
+    F: FnOnce(&mut DeriveInput, &Annotated) -> Result<TokenStream2>,
+{
+    let annotated = annotations(&input)?;
+    generate(&mut input, &annotated)
+}
+
+fn ord(input: &mut DeriveInput, annotated: &Annotated) -> Result<TokenStream2> {
+    let cost = annotated.cost(input)?;
+    let (left, right) = (cost.of(quote!(self)), cost.of(quote!(other)));
+
+    // Ranking by a method can't be bounded here, but ranking by the
+    // field itself needs the field to be ordered.
+    if cost.method.is_none() {
+        let ty = &cost.ty;
+        input
+            .generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote!(#ty: ::std::cmp::Ord));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::std::cmp::Ord for #name #ty_generics #where_clause {
+            fn cmp(&self, other: &Self) -> ::std::cmp::Ordering {
+                #left.cmp(&#right).reverse()
+            }
+        }
+
+        impl #impl_generics ::std::cmp::PartialOrd for #name #ty_generics #where_clause {
+            fn partial_cmp(&self, other: &Self) -> ::std::option::Option<::std::cmp::Ordering> {
+                ::std::option::Option::Some(::std::cmp::Ord::cmp(self, other))
+            }
+        }
+
+        impl #impl_generics ::std::cmp::PartialEq for #name #ty_generics #where_clause {
+            fn eq(&self, other: &Self) -> bool {
+                #left.eq(&#right)
+            }
+        }
+
+        impl #impl_generics ::std::cmp::Eq for #name #ty_generics #where_clause {}
+    })
+}
+
+fn state(input: &mut DeriveInput, annotated: &Annotated) -> Result<TokenStream2> {
+    let (ty, value) = match annotated.state.as_slice() {
+        [] => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "expected at least one field marked #[search(state)]",
+            ))
+        }
+        [(member, ty)] => (quote!(#ty), quote!(self.#member.clone())),
+        fields => {
+            let members = fields.iter().map(|(m, _)| m);
+            let types = fields.iter().map(|(_, t)| t);
+            (quote!((#(#types),*)), quote!((#(self.#members.clone()),*)))
+        }
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::searcher::SearchState for #name #ty_generics #where_clause {
+            type State = #ty;
+
+            fn state(&self) -> Self::State {
+                #value
+            }
+        }
+    })
+}
+
+fn cacher(input: &mut DeriveInput, annotated: &Annotated) -> Result<TokenStream2> {
+    let cost = annotated.cost(input)?;
+    let value = cost.of(quote!(self));
+    let ty = match (&cost.method, &cost.value) {
+        (_, Some(ty)) => ty.clone(),
+        (None, None) => cost.ty.clone(),
+        (Some(method), None) => {
+            return Err(Error::new_spanned(
+                method,
+                "add value = \"Type\" to give the type returned by the cost method",
+            ))
+        }
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::searcher::SearchCacher for #name #ty_generics #where_clause {
+            type Value = #ty;
+
+            fn value(&self) -> Self::Value {
+                ::std::clone::Clone::clone(&#value)
+            }
+        }
+    })
+}
@@ -1,19 +1,30 @@
 //! Provides the building blocks for search algorithms
 
-use std::collections::BinaryHeap;
 use std::default::Default;
+use std::fmt;
 use std::time;
 
-use self::cache::Cache;
+use self::cache::{Cache, Keep};
+use self::observer::{CancelToken, Observers, SearchEvent, SearchObserver};
+use self::results::Results;
 use self::score::Score;
 use crate::errors::{Result, SearchError};
 use crate::traits::{SearchCandidate, SearchScore};
 
 pub mod astar;
 pub mod basic;
+pub mod beam;
+pub mod bidirectional;
 pub mod cache;
+pub mod checkpoint;
 pub mod dijkstra;
+pub mod iterative;
+pub mod observer;
+pub mod parallel;
+mod results;
 pub mod score;
+pub mod sma;
+pub mod weighted;
 
 /// Trait used to implement queues of search candidates
 /// which should be checked for completion.
@@ -30,12 +41,22 @@ pub trait SearchQueue {
     fn can_terminate(&self, candidate: &Self::Candidate) -> bool {
         false
     }
+
+    /// Like [SearchQueue::can_terminate], but only once nothing left in
+    /// the queue could even tie with the candidate.
+    #[allow(unused_variables)]
+    fn can_terminate_ties(&self, candidate: &Self::Candidate) -> bool {
+        false
+    }
 }
 
 #[derive(Debug, Default)]
 struct TimeLimit {
     start: Option<time::Instant>,
     maximum: Option<time::Duration>,
+
+    // Time spent before the search was resumed from a checkpoint.
+    previous: time::Duration,
 }
 
 impl TimeLimit {
@@ -43,21 +64,22 @@ impl TimeLimit {
         Self {
             start: None,
             maximum: limit,
+            previous: time::Duration::default(),
+        }
+    }
+
+    fn resume(limit: Option<time::Duration>, previous: time::Duration) -> Self {
+        Self {
+            previous,
+            ..Self::new(limit)
         }
     }
 
     fn increment(&mut self) -> Result<()> {
-        if self.start.is_none() {
-            self.start = Some(time::Instant::now());
-        }
-        if self
-            .start
-            .map(|s| self.maximum.map(|m| s.elapsed() > m).unwrap_or(false))
-            .unwrap_or(false)
-        {
-            Err(SearchError::TimeLimitExhausted(
-                self.start.unwrap().elapsed(),
-            ))
+        let start = *self.start.get_or_insert_with(time::Instant::now);
+        let elapsed = self.previous + start.elapsed();
+        if self.maximum.map(|m| elapsed > m).unwrap_or(false) {
+            Err(SearchError::TimeLimitExhausted(elapsed))
         } else {
             Ok(())
         }
@@ -78,6 +100,13 @@ impl StepLimit {
         }
     }
 
+    fn resume(limit: Option<usize>, current: usize) -> Self {
+        Self {
+            current,
+            maximum: limit,
+        }
+    }
+
     fn increment(&mut self) -> Result<()> {
         self.current += 1;
 
@@ -105,6 +134,50 @@ impl SearchOptions {
     }
 }
 
+/// Measurements taken while a search runs, see
+/// [SearchAlgorithm::run_with_stats].
+#[derive(Debug, Default, Clone)]
+#[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchStats {
+    /// Candidates whose children were generated.
+    pub expanded: usize,
+
+    /// Candidates considered, including the origin.
+    pub generated: usize,
+
+    /// Candidates dropped because the cache had seen their state
+    /// at least as cheaply.
+    pub cache_hits: usize,
+
+    /// Candidates which the cache let through.
+    pub cache_misses: usize,
+
+    /// Largest number of candidates waiting in the queue at once.
+    pub peak_queue: usize,
+
+    pub elapsed: time::Duration,
+
+    /// Complete candidates found, whether or not they were the best.
+    pub complete: usize,
+}
+
+impl fmt::Display for SearchStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expanded {} generated {} cache {}/{} peak queue {} complete {} in {:?}",
+            self.expanded,
+            self.generated,
+            self.cache_hits,
+            self.cache_hits + self.cache_misses,
+            self.peak_queue,
+            self.complete,
+            self.elapsed
+        )
+    }
+}
+
 /// Implementation of search, using generic components.
 ///
 /// Uses a generic queue (Q) and a generic cache (C) to provide
@@ -118,11 +191,22 @@ where
 {
     cache: C,
     queue: Q,
-    results: BinaryHeap<S>,
+    results: Results<S>,
     counter: StepLimit,
     timer: TimeLimit,
     options: SearchOptions,
     origin: Option<S>,
+    stats: SearchStats,
+    observers: Observers<S>,
+    cancel: CancelToken,
+    started: Option<time::Instant>,
+
+    // A candidate whose expansion was interrupted by an error, and how
+    // many of its children had already been processed.
+    expanding: Option<(S, usize)>,
+
+    // Set whenever a complete candidate becomes the new best result.
+    improved: bool,
 }
 
 impl<S, Q, C> SearchAlgorithm<Score<S>, Q, C>
@@ -149,11 +233,17 @@ where
         let sr = SearchAlgorithm {
             cache: C::default(),
             queue: Q::default(),
-            results: BinaryHeap::default(),
+            results: Results::default(),
             counter: counter,
             timer: timer,
             options: options,
             origin: Some(origin),
+            stats: SearchStats::default(),
+            observers: Observers::default(),
+            cancel: CancelToken::default(),
+            started: None,
+            expanding: None,
+            improved: false,
         };
         sr
     }
@@ -162,17 +252,47 @@ where
         Self::new_with_options(origin, SearchOptions::default())
     }
 
+    /// Start a search with a queue which has already been configured,
+    /// e.g. with the width of a beam search.
+    pub fn new_with_queue(origin: S, queue: Q) -> Self {
+        let mut sr = Self::new(origin);
+        sr.queue = queue;
+        sr
+    }
+
     pub fn with_options(mut self, options: SearchOptions) -> Self {
-        let origin = self
-            .origin
-            .take()
-            .expect("Algorithm appears to have already started, no origin!");
+        assert!(
+            self.origin.is_some(),
+            "Algorithm appears to have already started, no origin!"
+        );
+
+        self.counter = StepLimit::new(options.limit);
+        self.timer = TimeLimit::new(options.maxtime);
+        self.options = options;
+        self
+    }
+
+    /// Call an observer for each [SearchEvent] in the search.
+    pub fn with_observer<O>(mut self, observer: O) -> Self
+    where
+        O: SearchObserver<S> + Send + 'static,
+    {
+        self.observers.push(Box::new(observer));
+        self
+    }
 
-        Self::new_with_options(origin, options)
+    /// Stop the search with [SearchError::Cancelled] once the token
+    /// is cancelled.
+    pub fn with_cancellation(mut self, token: CancelToken) -> Self {
+        self.cancel = token;
+        self
     }
 
-    fn best(&self) -> Option<&S> {
-        self.results.peek()
+    /// Use a cache which has already been configured, e.g. with the
+    /// capacity of an [cache::LruCache].
+    pub fn with_cache(mut self, cache: C) -> Self {
+        self.cache = cache;
+        self
     }
 
     // Should we continue searching from this candidate?
@@ -180,61 +300,280 @@ where
         // Increment the step counter
         self.counter.increment()?;
         self.timer.increment()?;
+        self.cancel.check()?;
+        self.stats.generated += 1;
 
         // If we found an answer, we can stop hunting now
         // and add the answer to our search results.
         if candidate.is_complete() {
-            self.results.push(candidate);
+            self.stats.complete += 1;
+            self.observers.notify(SearchEvent::Completed(&candidate));
+            self.improved |= !self.results.prunes(&candidate);
+            self.results.insert(candidate);
             return Ok(None);
         }
 
         // Scores can only increase in searches, if the best candidate
         // is better than our current guess, give up now.
-        if self.best().map(|s| &candidate >= s).unwrap_or(false) {
+        if self.results.prunes(&candidate) {
             return Ok(None);
         }
 
-        if self.cache.check(&candidate)? {
+        if self.cache.check_keep(&candidate, self.results.keep())? {
+            self.stats.cache_misses += 1;
             return Ok(Some(candidate));
         }
+        self.stats.cache_hits += 1;
         Ok(None)
     }
 
+    fn push(&mut self, candidate: S) {
+        self.observers.notify(SearchEvent::Pushed(&candidate));
+        self.queue.push(candidate);
+        self.stats.peak_queue = self.stats.peak_queue.max(self.queue.len());
+    }
+
     fn show_debug_msg(&self, n: usize) -> bool {
         self.options.verbose.map(|v| n % v == 0).unwrap_or(false)
     }
 
     /// Run the search to completion.
-    pub fn run(mut self) -> Result<S> {
-        let mut n = 0;
-        let origin = self.origin.take().unwrap();
+    pub fn run(self) -> Result<S> {
+        self.run_with_stats().map(|(result, _)| result)
+    }
 
-        if let Some(c) = self.process_candidate(origin)? {
-            self.queue.push(c);
+    /// Run the search to completion, returning the result along with
+    /// statistics about the work done to find it.
+    pub fn run_with_stats(mut self) -> Result<(S, SearchStats)> {
+        self.search(Keep::Best(1))?;
+
+        // The search may have finished without taking a step.
+        if let Some(start) = self.started {
+            self.stats.elapsed = start.elapsed();
+        }
+        let result = self.results.into_sorted().into_iter().next();
+        Ok((result.ok_or(SearchError::NoResultFound)?, self.stats))
+    }
+
+    /// Run the search until every result tied for the best has been
+    /// found, and return them all.
+    pub fn run_all_optimal(mut self) -> Result<impl Iterator<Item = S>> {
+        self.search(Keep::Ties)?;
+        self.into_results()
+    }
+
+    /// Run the search until the `k` best results have been found, and
+    /// return them, best first.
+    ///
+    /// Each state may be explored up to `k` times, so this is
+    /// considerably slower than finding a single result.
+    pub fn run_k_best(mut self, k: usize) -> Result<impl Iterator<Item = S>> {
+        self.search(Keep::Best(k))?;
+        self.into_results()
+    }
+
+    fn into_results(self) -> Result<std::vec::IntoIter<S>> {
+        match self.results.into_sorted() {
+            results if results.is_empty() => Err(SearchError::NoResultFound),
+            results => Ok(results.into_iter()),
+        }
+    }
+
+    /// Iterate over each new best result as soon as it is found.
+    ///
+    /// The search carries on refining its result until it is exhausted,
+    /// so the last result is the best one. If a limit stops the search
+    /// first, the error is the final item.
+    pub fn run_anytime(self) -> Anytime<S, Q, C>
+    where
+        S: Clone,
+    {
+        Anytime {
+            search: self,
+            done: false,
         }
+    }
 
-        while let Some(candidate) = self.queue.pop() {
-            n += 1;
+    fn search(&mut self, keep: Keep) -> Result<()> {
+        self.begin(keep)?;
+        while self.step()? {}
+        Ok(())
+    }
 
-            if self.show_debug_msg(n) {
-                eprintln!("Q{} R{} {}", self.queue.len(), self.results.len(), n);
+    fn begin(&mut self, keep: Keep) -> Result<()> {
+        let previous = std::mem::take(&mut self.results).into_sorted();
+        self.results = Results::new(keep);
+        let now = time::Instant::now();
+        self.started = Some(now.checked_sub(self.stats.elapsed).unwrap_or(now));
+
+        // A search resumed from a checkpoint carries on where it left off.
+        let origin = match self.origin.take() {
+            Some(origin) => origin,
+            None => {
+                previous.into_iter().for_each(|r| self.results.insert(r));
+                return Ok(());
             }
+        };
 
-            for child in candidate.children() {
-                if let Some(c) = self.process_candidate(child)? {
-                    self.queue.push(c);
+        if let Some(c) = self.process_candidate(origin)? {
+            self.push(c);
+        }
+        Ok(())
+    }
+
+    // Expand the next candidate, returning false once the search is over.
+    fn step(&mut self) -> Result<bool> {
+        // Finish an interrupted expansion before taking anything new.
+        let (candidate, done) = match self.expanding.take() {
+            Some(expanding) => expanding,
+            None => match self.queue.pop() {
+                Some(c) => {
+                    self.stats.expanded += 1;
+                    self.observers.notify(SearchEvent::Expanded(&c));
+                    (c, 0)
+                }
+                None => return Ok(false),
+            },
+        };
+
+        if done == 0 && self.show_debug_msg(self.stats.expanded) {
+            eprintln!(
+                "Q{} R{} {}",
+                self.queue.len(),
+                self.results.len(),
+                self.stats.expanded
+            );
+        }
+
+        for (i, child) in candidate.children().into_iter().enumerate().skip(done) {
+            match self.process_candidate(child) {
+                Ok(Some(c)) => self.push(c),
+                Ok(None) => {}
+                Err(error) => {
+                    // Carry on from this child if the search is resumed.
+                    self.expanding = Some((candidate, i));
+                    return Err(error);
                 }
             }
-            if !self.options.exhaustive
-                && self
-                    .best()
-                    .map(|c| self.queue.can_terminate(c))
-                    .unwrap_or(false)
-            {
-                break;
+        }
+        if let Some(start) = self.started {
+            self.stats.elapsed = start.elapsed();
+        }
+
+        Ok(self.options.exhaustive || !self.results.can_terminate(&self.queue))
+    }
+}
+
+/// Iterator over improving results, see [SearchAlgorithm::run_anytime].
+#[derive(Debug)]
+pub struct Anytime<S, Q, C>
+where
+    S: SearchCandidate + Ord,
+    Q: SearchQueue<Candidate = S> + Default,
+    C: Cache<Candidate = S>,
+{
+    search: SearchAlgorithm<S, Q, C>,
+    done: bool,
+}
+
+impl<S, Q, C> Anytime<S, Q, C>
+where
+    S: SearchCandidate + Ord + Clone,
+    Q: SearchQueue<Candidate = S> + Default,
+    C: Cache<Candidate = S>,
+{
+    /// Statistics for the search so far.
+    pub fn stats(&self) -> &SearchStats {
+        &self.search.stats
+    }
+
+    fn advance(&mut self) -> Result<Option<S>> {
+        if self.search.started.is_none() {
+            self.search.begin(Keep::Best(1))?;
+        } else if !self.search.step()? {
+            self.done = true;
+        }
+
+        if std::mem::take(&mut self.search.improved) {
+            return Ok(self.search.results.best().cloned());
+        }
+        Ok(None)
+    }
+}
+
+impl<S, Q, C> Iterator for Anytime<S, Q, C>
+where
+    S: SearchCandidate + Ord + Clone,
+    Q: SearchQueue<Candidate = S> + Default,
+    C: Cache<Candidate = S>,
+{
+    type Item = Result<S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.advance() {
+                Ok(Some(result)) => return Some(Ok(result)),
+                Ok(None) => {}
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
             }
         }
+        None
+    }
+}
 
-        self.results.pop().ok_or(SearchError::NoResultFound)
+/// The result of a search which trades optimality for speed.
+#[derive(Debug, Clone)]
+pub struct Approximate<S> {
+    pub candidate: S,
+
+    /// Whether the candidate is known to be the best possible result.
+    /// When false, a better result may exist.
+    pub optimal: bool,
+}
+
+impl<S> Approximate<S> {
+    pub fn unwrap(self) -> S {
+        self.candidate
+    }
+}
+
+/// A search algorithm whose result is reported as [Approximate].
+#[derive(Debug)]
+pub struct ApproximateSearch<S, Q, C>
+where
+    S: SearchCandidate + Ord,
+    Q: SearchQueue<Candidate = S> + Default,
+    C: Cache<Candidate = S>,
+{
+    algorithm: SearchAlgorithm<S, Q, C>,
+    optimal: bool,
+}
+
+impl<S, Q, C> ApproximateSearch<S, Q, C>
+where
+    S: SearchCandidate + Ord,
+    Q: SearchQueue<Candidate = S> + Default,
+    C: Cache<Candidate = S>,
+{
+    pub(crate) fn new(algorithm: SearchAlgorithm<S, Q, C>, optimal: bool) -> Self {
+        Self { algorithm, optimal }
+    }
+
+    pub fn with_options(self, options: SearchOptions) -> Self {
+        Self {
+            algorithm: self.algorithm.with_options(options),
+            optimal: self.optimal,
+        }
+    }
+
+    /// Run the search to completion.
+    pub fn run(self) -> Result<Approximate<S>> {
+        let optimal = self.optimal;
+        self.algorithm
+            .run()
+            .map(|candidate| Approximate { candidate, optimal })
     }
 }
@@ -12,12 +12,25 @@ use crate::traits::{SearchCacher, SearchHeuristic};
 use crate::{algorithm::SearchQueue, SearchCandidate};
 
 #[derive(Debug)]
-struct Heuristic<S>
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Heuristic<S>
 where
     S: SearchHeuristic,
 {
-    heuristic: S::Hueristic,
-    candidate: S,
+    pub(crate) heuristic: S::Hueristic,
+    pub(crate) candidate: S,
+}
+
+impl<S> Heuristic<S>
+where
+    S: SearchHeuristic,
+{
+    pub(crate) fn new(candidate: S) -> Self {
+        Heuristic {
+            heuristic: candidate.heuristic(),
+            candidate,
+        }
+    }
 }
 
 impl<S> PartialEq for Heuristic<S>
@@ -64,6 +77,14 @@ where
 /// search candidate based on a heuristic from [SearchHeuristic]
 /// and so implements A* search.
 #[derive(Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "Heuristic<S>: serde::Serialize",
+        deserialize = "Heuristic<S>: serde::Deserialize<'de>"
+    ))
+)]
 pub struct AStarQueue<S>
 where
     S: SearchHeuristic,
@@ -82,15 +103,28 @@ where
     }
 
     fn push(&mut self, item: Self::Candidate) {
-        self.queue.push(Heuristic {
-            heuristic: item.heuristic(),
-            candidate: item,
-        });
+        self.queue.push(Heuristic::new(item));
     }
 
     fn len(&self) -> usize {
         self.queue.len()
     }
+
+    // With a heuristic which never overestimates, nothing left in the
+    // queue can beat a result which is estimated to be no worse.
+    fn can_terminate(&self, candidate: &Self::Candidate) -> bool {
+        self.queue
+            .peek()
+            .map(|h| h.heuristic >= candidate.heuristic())
+            .unwrap_or(true)
+    }
+
+    fn can_terminate_ties(&self, candidate: &Self::Candidate) -> bool {
+        self.queue
+            .peek()
+            .map(|h| h.heuristic > candidate.heuristic())
+            .unwrap_or(true)
+    }
 }
 
 type AStarSearcher<S> = SearchAlgorithm<S, AStarQueue<S>, BasicCache<S>>;
@@ -12,6 +12,7 @@ pub mod bfs {
     use crate::SearchCandidate;
 
     #[derive(Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct BreadthQueue<S> {
         queue: VecDeque<S>,
     }
@@ -69,6 +70,7 @@ pub mod dfs {
     use crate::errors::Result;
     use crate::SearchCandidate;
     #[derive(Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct DepthQueue<S> {
         queue: VecDeque<S>,
     }
@@ -0,0 +1,201 @@
+//! Beam Search
+//!
+//! Searches level by level like breadth-first search, but only keeps the
+//! best few candidates at each level, as ranked by [SearchHeuristic].
+//! Memory and time are bounded by the width of the beam, at the cost of
+//! possibly discarding the path to the best result.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::default::Default;
+
+use super::astar::Heuristic;
+use super::cache::BasicCache;
+use super::{ApproximateSearch, SearchAlgorithm, SearchQueue};
+use crate::errors::Result;
+use crate::traits::{SearchCacher, SearchCandidate, SearchHeuristic};
+use crate::Approximate;
+
+/// Width used by [BeamQueue::default].
+pub const DEFAULT_WIDTH: usize = 100;
+
+/// A queue which expands one level of the search at a time, and keeps
+/// only the best `width` candidates found for the next level.
+#[derive(Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "S: serde::Serialize, Heuristic<S>: serde::Serialize",
+        deserialize = "S: serde::Deserialize<'de>, Heuristic<S>: serde::Deserialize<'de>"
+    ))
+)]
+pub struct BeamQueue<S>
+where
+    S: SearchHeuristic,
+{
+    width: usize,
+    current: VecDeque<S>,
+
+    // The worst candidate is at the top, so that it is the one dropped
+    // when the next level is full.
+    next: BinaryHeap<Reverse<Heuristic<S>>>,
+}
+
+impl<S> BeamQueue<S>
+where
+    S: SearchHeuristic,
+{
+    pub fn new(width: usize) -> Self {
+        BeamQueue {
+            width: width.max(1),
+            current: VecDeque::new(),
+            next: BinaryHeap::new(),
+        }
+    }
+
+    // Move on to the next level, best candidates first.
+    fn advance(&mut self) {
+        let mut level: Vec<_> = self.next.drain().map(|Reverse(h)| h).collect();
+        level.sort_by(|a, b| b.cmp(a));
+        self.current = level.into_iter().map(|h| h.candidate).collect();
+    }
+}
+
+impl<S> Default for BeamQueue<S>
+where
+    S: SearchHeuristic,
+{
+    fn default() -> Self {
+        BeamQueue::new(DEFAULT_WIDTH)
+    }
+}
+
+impl<S> SearchQueue for BeamQueue<S>
+where
+    S: SearchHeuristic,
+{
+    type Candidate = S;
+
+    fn pop(&mut self) -> Option<Self::Candidate> {
+        if self.current.is_empty() {
+            self.advance();
+        }
+        self.current.pop_front()
+    }
+
+    fn push(&mut self, item: Self::Candidate) {
+        self.next.push(Reverse(Heuristic::new(item)));
+        if self.next.len() > self.width {
+            self.next.pop();
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.current.len() + self.next.len()
+    }
+
+    // Stop once no remaining candidate is estimated to beat the best result.
+    fn can_terminate(&self, candidate: &Self::Candidate) -> bool {
+        let best = candidate.heuristic();
+        self.current.iter().all(|c| c.heuristic() >= best)
+            && self.next.iter().all(|Reverse(h)| h.heuristic >= best)
+    }
+
+    fn can_terminate_ties(&self, candidate: &Self::Candidate) -> bool {
+        let best = candidate.heuristic();
+        self.current.iter().all(|c| c.heuristic() > best)
+            && self.next.iter().all(|Reverse(h)| h.heuristic > best)
+    }
+}
+
+type BeamSearcher<S> = ApproximateSearch<S, BeamQueue<S>, BasicCache<S>>;
+
+pub fn build<S>(origin: S, width: usize) -> BeamSearcher<S>
+where
+    S: SearchHeuristic + SearchCandidate + SearchCacher + Ord,
+{
+    ApproximateSearch::new(
+        SearchAlgorithm::new_with_queue(origin, BeamQueue::new(width)),
+        false,
+    )
+}
+
+/// Perform a beam search, keeping `width` candidates at each level.
+///
+/// The result is never reported as optimal, since the best path may
+/// have been dropped from the beam.
+pub fn run<S>(origin: S, width: usize) -> Result<Approximate<S>>
+where
+    S: SearchHeuristic + SearchCandidate + SearchCacher + Ord,
+{
+    build(origin, width).run()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar, SearchCacher, SearchOrd, SearchState};
+
+    // A small graph where the cheapest first step leads to an expensive
+    // last step: S -> A -> G costs 11, S -> B -> G costs 4.
+    #[derive(Debug, Clone, SearchOrd, SearchState, SearchCacher)]
+    struct Detour {
+        #[search(state)]
+        node: char,
+        #[search(cost)]
+        cost: usize,
+    }
+
+    impl SearchCandidate for Detour {
+        fn is_complete(&self) -> bool {
+            self.node == 'G'
+        }
+
+        fn children(&self) -> Vec<Self> {
+            let edges: &[(char, usize)] = match self.node {
+                'S' => &[('A', 1), ('B', 2)],
+                'A' => &[('G', 10)],
+                'B' => &[('G', 2)],
+                _ => &[],
+            };
+            edges
+                .iter()
+                .map(|&(node, cost)| Detour {
+                    node,
+                    cost: self.cost + cost,
+                })
+                .collect()
+        }
+    }
+
+    impl SearchHeuristic for Detour {
+        type Hueristic = usize;
+
+        fn heuristic(&self) -> usize {
+            let remaining = match self.node {
+                'S' | 'B' => 2,
+                _ => 0,
+            };
+            self.cost + remaining
+        }
+    }
+
+    fn origin() -> Detour {
+        Detour { node: 'S', cost: 0 }
+    }
+
+    #[test]
+    fn narrow() {
+        let best = astar::run(origin()).unwrap();
+        assert_eq!(best.cost, 4);
+
+        // A beam of one only keeps A, which looks better than B.
+        let found = run(origin(), 1).unwrap();
+        assert!(!found.optimal);
+        assert_eq!(found.unwrap().cost, 11);
+
+        let found = run(origin(), 2).unwrap();
+        assert_eq!(found.unwrap().cost, 4);
+    }
+}
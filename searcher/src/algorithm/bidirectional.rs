@@ -0,0 +1,291 @@
+//! Bidirectional Dijkstra's Algorithm
+//!
+//! Two searches run at once, one forward from the origin and one backward
+//! from the goal. Each step expands whichever frontier is cheaper, so that
+//! on long corridors the two searches cover about half the ground that a
+//! single search would.
+
+use std::collections::{BinaryHeap, HashMap};
+use std::ops::Add;
+
+use super::{SearchOptions, StepLimit, TimeLimit};
+use crate::errors::{Result, SearchError};
+use crate::traits::{SearchCandidate, SearchReversible};
+
+#[derive(Debug)]
+struct Frontier<S>
+where
+    S: SearchReversible + Ord,
+{
+    queue: BinaryHeap<S>,
+    best: HashMap<S::State, S>,
+}
+
+impl<S> Frontier<S>
+where
+    S: SearchReversible + Ord,
+{
+    fn new(origin: S) -> Self {
+        let mut best = HashMap::new();
+        best.insert(origin.state(), origin.clone());
+        let mut queue = BinaryHeap::new();
+        queue.push(origin);
+        Frontier { queue, best }
+    }
+
+    fn top(&self) -> Option<S::Value> {
+        self.queue.peek().map(|c| c.value())
+    }
+
+    // Pop the cheapest candidate, skipping those which have since been
+    // beaten by a cheaper way to reach the same state.
+    fn pop(&mut self) -> Option<S> {
+        while let Some(candidate) = self.queue.pop() {
+            let stale = self
+                .best
+                .get(&candidate.state())
+                .map(|b| b.value() < candidate.value())
+                .unwrap_or(false);
+            if !stale {
+                return Some(candidate);
+            }
+        }
+        None
+    }
+
+    // Record a candidate if it is the cheapest way to its state so far.
+    fn insert(&mut self, candidate: S) -> bool {
+        let state = candidate.state();
+        if let Some(b) = self.best.get(&state) {
+            if b.value() <= candidate.value() {
+                return false;
+            }
+        }
+        self.best.insert(state, candidate.clone());
+        self.queue.push(candidate);
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Forward,
+    Backward,
+}
+
+/// Search which meets in the middle, see [run].
+#[derive(Debug)]
+pub struct BidirectionalSearch<S>
+where
+    S: SearchReversible + Ord,
+{
+    counter: StepLimit,
+    timer: TimeLimit,
+    options: SearchOptions,
+    origins: Option<(S, S)>,
+}
+
+impl<S> BidirectionalSearch<S>
+where
+    S: SearchCandidate + SearchReversible + Ord,
+    S::Value: Add<Output = S::Value>,
+{
+    pub fn new_with_options(forward: S, backward: S, options: SearchOptions) -> Self {
+        BidirectionalSearch {
+            counter: StepLimit::new(options.limit),
+            timer: TimeLimit::new(options.maxtime),
+            options,
+            origins: Some((forward, backward)),
+        }
+    }
+
+    pub fn new(forward: S, backward: S) -> Self {
+        Self::new_with_options(forward, backward, SearchOptions::default())
+    }
+
+    pub fn with_options(mut self, options: SearchOptions) -> Self {
+        let (forward, backward) = self
+            .origins
+            .take()
+            .expect("Algorithm appears to have already started, no origin!");
+
+        Self::new_with_options(forward, backward, options)
+    }
+
+    /// Run the search to completion.
+    pub fn run(mut self) -> Result<S> {
+        let (forward, backward) = self.origins.take().unwrap();
+
+        let mut best: Option<S> = None;
+        if forward.state() == backward.state() {
+            best = Some(forward.join(&backward));
+        }
+
+        let mut frontiers = (Frontier::new(forward), Frontier::new(backward));
+        let mut n = 0;
+
+        while let (Some(f), Some(b)) = (frontiers.0.top(), frontiers.1.top()) {
+            // Any path found from here on must pass through both frontiers,
+            // so it can't be cheaper than the sum of their cheapest entries.
+            if best
+                .as_ref()
+                .map(|c| f.clone() + b.clone() >= c.value())
+                .unwrap_or(false)
+            {
+                break;
+            }
+
+            n += 1;
+            if self.options.verbose.map(|v| n % v == 0).unwrap_or(false) {
+                eprintln!(
+                    "F{} B{} {}",
+                    frontiers.0.queue.len(),
+                    frontiers.1.queue.len(),
+                    n
+                );
+            }
+
+            let direction = if f <= b {
+                Direction::Forward
+            } else {
+                Direction::Backward
+            };
+            let (this, other) = match direction {
+                Direction::Forward => (&mut frontiers.0, &frontiers.1),
+                Direction::Backward => (&mut frontiers.1, &frontiers.0),
+            };
+
+            let candidate = match this.pop() {
+                Some(c) => c,
+                None => continue,
+            };
+            self.counter.increment()?;
+            self.timer.increment()?;
+
+            for child in candidate.children() {
+                let meeting = other.best.get(&child.state()).map(|o| match direction {
+                    Direction::Forward => child.join(o),
+                    Direction::Backward => o.join(&child),
+                });
+
+                if let Some(joined) = meeting {
+                    if best
+                        .as_ref()
+                        .map(|c| joined.value() < c.value())
+                        .unwrap_or(true)
+                    {
+                        best = Some(joined);
+                    }
+                }
+                this.insert(child);
+            }
+        }
+
+        best.ok_or(SearchError::NoResultFound)
+    }
+}
+
+pub fn build<S>(forward: S, backward: S) -> BidirectionalSearch<S>
+where
+    S: SearchCandidate + SearchReversible + Ord,
+    S::Value: Add<Output = S::Value>,
+{
+    BidirectionalSearch::new(forward, backward)
+}
+
+/// Perform a bidirectional search using Dijkstra's algorithm from both ends.
+///
+/// `forward` starts at the origin and `backward` starts at the goal. The
+/// search stops once no path through the unexplored frontiers could be
+/// cheaper than the best joined path, which is then returned.
+pub fn run<S>(forward: S, backward: S) -> Result<S>
+where
+    S: SearchCandidate + SearchReversible + Ord,
+    S::Value: Add<Output = S::Value>,
+{
+    build(forward, backward).run()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SearchCacher, SearchOrd, SearchState};
+
+    // Walks along a line of cells, some of which are walls.
+    #[derive(Debug, Clone, SearchOrd, SearchState, SearchCacher)]
+    struct Corridor<'c> {
+        #[search(state)]
+        position: usize,
+        #[search(cost)]
+        steps: usize,
+        path: Vec<usize>,
+        walls: &'c [bool],
+    }
+
+    impl<'c> Corridor<'c> {
+        fn start(position: usize, walls: &'c [bool]) -> Self {
+            Corridor {
+                position,
+                steps: 0,
+                path: vec![position],
+                walls,
+            }
+        }
+    }
+
+    impl<'c> SearchCandidate for Corridor<'c> {
+        fn is_complete(&self) -> bool {
+            false
+        }
+
+        fn children(&self) -> Vec<Self> {
+            let mut children = Vec::new();
+            for next in [self.position.wrapping_sub(1), self.position + 1].iter() {
+                if self.walls.get(*next) == Some(&false) {
+                    let mut path = self.path.clone();
+                    path.push(*next);
+                    children.push(Corridor {
+                        position: *next,
+                        steps: self.steps + 1,
+                        path,
+                        walls: self.walls,
+                    });
+                }
+            }
+            children
+        }
+    }
+
+    impl<'c> SearchReversible for Corridor<'c> {
+        fn join(&self, backward: &Self) -> Self {
+            let mut joined = self.clone();
+            joined.path.extend(backward.path.iter().rev().skip(1));
+            joined.position = backward.path[0];
+            joined.steps = self.steps + backward.steps;
+            joined
+        }
+    }
+
+    #[test]
+    fn meet_in_the_middle() {
+        let walls = [false; 10];
+        let found = run(Corridor::start(1, &walls), Corridor::start(8, &walls)).unwrap();
+        assert_eq!(found.steps, 7);
+        assert_eq!(found.path, (1..=8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn same_place() {
+        let walls = [false; 3];
+        let found = run(Corridor::start(1, &walls), Corridor::start(1, &walls)).unwrap();
+        assert_eq!(found.steps, 0);
+        assert_eq!(found.path, vec![1]);
+    }
+
+    #[test]
+    fn blocked() {
+        let walls = [false, false, true, false];
+        let result = run(Corridor::start(0, &walls), Corridor::start(3, &walls));
+        assert!(matches!(result, Err(SearchError::NoResultFound)));
+    }
+}
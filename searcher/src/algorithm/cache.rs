@@ -1,21 +1,46 @@
 //! Search cacheing support to eliminate already explored items.
 
-use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap};
 use std::default::Default;
+use std::hash::BuildHasher;
 use std::marker::PhantomData;
+use std::mem;
+use std::sync::Mutex;
 
 use crate::errors::Result;
 use crate::traits::{SearchCacher, SearchCandidate};
 
+/// Which complete candidates a search should keep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keep {
+    /// The best `k` candidates.
+    Best(usize),
+
+    /// Every candidate tied for the best.
+    Ties,
+}
+
 /// Defines the behavior required of a search cache.
 pub trait Cache: Default {
     type Candidate: SearchCandidate;
 
     fn check(&mut self, candidate: &Self::Candidate) -> Result<bool>;
+
+    /// Check a candidate when more than one result is wanted. States
+    /// must then be revisited by candidates which tie with, or are
+    /// among the `k` best for, the state, as set by [Keep].
+    fn check_keep(&mut self, candidate: &Self::Candidate, keep: Keep) -> Result<bool> {
+        match keep {
+            Keep::Best(1) => self.check(candidate),
+            _ => Ok(true),
+        }
+    }
 }
 
 /// Provides no-op caching.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NoCache<T>(PhantomData<T>);
 
 impl<T> Default for NoCache<T> {
@@ -38,11 +63,23 @@ where
 /// Provides a simple hashmap cache which
 /// will store every search state encountered.
 #[derive(Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "S::State: serde::Serialize, S::Value: serde::Serialize",
+        deserialize = "S::State: serde::Deserialize<'de>, S::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct BasicCache<S>
 where
     S: SearchCacher,
 {
     cache: HashMap<S::State, S::Value>,
+
+    // Values of the best few candidates for each state, in order,
+    // used when keeping more than one result.
+    visits: HashMap<S::State, Vec<S::Value>>,
 }
 
 impl<S> Default for BasicCache<S>
@@ -52,6 +89,7 @@ where
     fn default() -> Self {
         BasicCache {
             cache: HashMap::default(),
+            visits: HashMap::default(),
         }
     }
 }
@@ -87,4 +125,323 @@ where
 
         return Ok(r);
     }
+
+    fn check_keep(&mut self, candidate: &Self::Candidate, keep: Keep) -> Result<bool> {
+        let k = match keep {
+            Keep::Best(1) => return self.check(candidate),
+            Keep::Best(k) => k,
+            Keep::Ties => {
+                // As for a single result, but ties also pass.
+                let value = candidate.value();
+                let e = self.cache.entry(candidate.state()).or_insert(value.clone());
+                if *e < value {
+                    return Ok(false);
+                }
+                *e = value;
+                return Ok(true);
+            }
+        };
+
+        let value = candidate.value();
+        let visits = self.visits.entry(candidate.state()).or_default();
+        if visits.len() >= k {
+            if visits.last().map(|w| *w <= value).unwrap_or(false) {
+                return Ok(false);
+            }
+            visits.pop();
+        }
+        let i = visits.partition_point(|v| *v <= value);
+        visits.insert(i, value);
+        Ok(true)
+    }
+}
+
+/// Number of shards used by [ConcurrentCache::default].
+pub const DEFAULT_SHARDS: usize = 64;
+
+/// A cache like [BasicCache] which can be checked from many threads at
+/// once. States are spread over several independently locked shards,
+/// so that threads rarely wait on each other.
+#[derive(Debug)]
+pub struct ConcurrentCache<S>
+where
+    S: SearchCacher,
+{
+    hasher: RandomState,
+    shards: Vec<Mutex<HashMap<S::State, S::Value>>>,
+}
+
+impl<S> ConcurrentCache<S>
+where
+    S: SearchCacher,
+{
+    pub fn new(shards: usize) -> Self {
+        ConcurrentCache {
+            hasher: RandomState::new(),
+            shards: (0..shards.max(1))
+                .map(|_| Mutex::new(HashMap::default()))
+                .collect(),
+        }
+    }
+
+    /// Same as [Cache::check] for [BasicCache], but only needs
+    /// shared access to the cache.
+    pub fn check(&self, candidate: &S) -> Result<bool> {
+        let state = candidate.state();
+        let value = candidate.value();
+        let shard = self.hasher.hash_one(&state) as usize % self.shards.len();
+
+        // A poisoned shard only means another thread panicked mid-search,
+        // the map itself is still consistent.
+        let mut cache = self.shards[shard]
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let mut r = true;
+        cache
+            .entry(state)
+            .and_modify(|e| {
+                if *e > value {
+                    *e = value.clone();
+                } else {
+                    r = false;
+                }
+            })
+            .or_insert_with(|| value.clone());
+
+        Ok(r)
+    }
+
+    /// Number of states recorded in the cache.
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|s| s.lock().map(|c| c.len()).unwrap_or(0))
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<S> Default for ConcurrentCache<S>
+where
+    S: SearchCacher,
+{
+    fn default() -> Self {
+        ConcurrentCache::new(DEFAULT_SHARDS)
+    }
+}
+
+/// Measures how much of a bounded cache's capacity an entry uses.
+pub trait Weigh<S>
+where
+    S: SearchCacher,
+{
+    fn weigh(state: &S::State, value: &S::Value) -> usize;
+}
+
+/// Counts each entry as one, so that capacity is a number of states.
+#[derive(Debug)]
+pub struct Entries;
+
+impl<S> Weigh<S> for Entries
+where
+    S: SearchCacher,
+{
+    fn weigh(_state: &S::State, _value: &S::Value) -> usize {
+        1
+    }
+}
+
+/// Weighs each entry by the bytes it occupies inline, along with the
+/// bookkeeping needed to track it. Memory owned by states on the heap,
+/// e.g. in a `Vec`, isn't counted.
+#[derive(Debug)]
+pub struct Bytes;
+
+impl<S> Weigh<S> for Bytes
+where
+    S: SearchCacher,
+{
+    fn weigh(_state: &S::State, _value: &S::Value) -> usize {
+        // Each state is held twice, once in the map and once in
+        // the recency order.
+        2 * mem::size_of::<S::State>() + mem::size_of::<S::Value>() + 3 * mem::size_of::<u64>()
+    }
+}
+
+/// Capacity used by [LruCache::default], in units of its [Weigh].
+pub const DEFAULT_CAPACITY: usize = 1 << 20;
+
+/// A cache like [BasicCache], but which forgets the least recently
+/// used states once it is full.
+///
+/// Forgotten states may be explored again, so a search using this cache
+/// does more work than with an unbounded one, but finds the same result.
+#[derive(Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "S::State: serde::Serialize, S::Value: serde::Serialize",
+        deserialize = "S::State: serde::Deserialize<'de>, S::Value: serde::Deserialize<'de>"
+    ))
+)]
+pub struct LruCache<S, W = Entries>
+where
+    S: SearchCacher,
+{
+    capacity: usize,
+    used: usize,
+    clock: u64,
+    cache: HashMap<S::State, (S::Value, u64)>,
+    recent: BTreeMap<u64, S::State>,
+    weigh: PhantomData<W>,
+}
+
+/// An [LruCache] whose capacity is in bytes.
+pub type ByteCache<S> = LruCache<S, Bytes>;
+
+impl<S, W> LruCache<S, W>
+where
+    S: SearchCacher,
+    S::State: Clone,
+    W: Weigh<S>,
+{
+    pub fn new(capacity: usize) -> Self {
+        LruCache {
+            capacity,
+            used: 0,
+            clock: 0,
+            cache: HashMap::new(),
+            recent: BTreeMap::new(),
+            weigh: PhantomData,
+        }
+    }
+
+    /// Number of states currently remembered.
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    // Record the state as the most recently used.
+    fn touch(&mut self, state: &S::State, value: S::Value) {
+        self.clock += 1;
+        if let Some((_, used)) = self.cache.get(state) {
+            self.recent.remove(used);
+        } else {
+            self.used += W::weigh(state, &value);
+        }
+        self.cache.insert(state.clone(), (value, self.clock));
+        self.recent.insert(self.clock, state.clone());
+    }
+
+    fn evict(&mut self) {
+        while self.used > self.capacity {
+            let state = match self.recent.pop_first() {
+                Some((_, state)) => state,
+                None => break,
+            };
+            if let Some((value, _)) = self.cache.remove(&state) {
+                self.used -= W::weigh(&state, &value);
+            }
+        }
+    }
+}
+
+impl<S, W> Default for LruCache<S, W>
+where
+    S: SearchCacher,
+    S::State: Clone,
+    W: Weigh<S>,
+{
+    fn default() -> Self {
+        LruCache::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl<S, W> Cache for LruCache<S, W>
+where
+    S: SearchCacher + Ord,
+    S::State: Clone,
+    W: Weigh<S>,
+{
+    type Candidate = S;
+
+    fn check(&mut self, candidate: &Self::Candidate) -> Result<bool> {
+        let state = candidate.state();
+        let value = candidate.value();
+
+        let seen = self.cache.get(&state).map(|(v, _)| v.clone());
+        match seen {
+            Some(v) if v <= value => {
+                self.touch(&state, v);
+                Ok(false)
+            }
+            _ => {
+                self.touch(&state, value);
+                self.evict();
+                Ok(true)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::dijkstra::DijkstraQueue;
+    use crate::algorithm::SearchAlgorithm;
+    use crate::dijkstra;
+    use crate::testing::LineWalker;
+
+    fn walker(position: i32, cost: i32) -> LineWalker {
+        LineWalker {
+            position,
+            cost,
+            target: 9,
+        }
+    }
+
+    #[test]
+    fn lru() {
+        let mut cache: LruCache<LineWalker> = LruCache::new(2);
+        assert!(cache.check(&walker(1, 1)).unwrap());
+        assert!(cache.check(&walker(2, 1)).unwrap());
+
+        // Using 1 again leaves 2 as the least recently used.
+        assert!(!cache.check(&walker(1, 3)).unwrap());
+        assert!(cache.check(&walker(3, 1)).unwrap());
+        assert_eq!(cache.len(), 2);
+
+        assert!(!cache.check(&walker(1, 3)).unwrap());
+        assert!(cache.check(&walker(2, 3)).unwrap());
+    }
+
+    #[test]
+    fn bytes() {
+        let entry = <Bytes as Weigh<LineWalker>>::weigh(&0, &0);
+        let mut cache: ByteCache<LineWalker> = LruCache::new(3 * entry);
+        for position in 0..5 {
+            assert!(cache.check(&walker(position, 1)).unwrap());
+        }
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn bounded_search() {
+        let expected = dijkstra::run(walker(0, 0)).unwrap();
+
+        let found = SearchAlgorithm::<_, DijkstraQueue<_>, _>::new(walker(0, 0))
+            .with_cache(LruCache::<LineWalker>::new(2))
+            .run()
+            .unwrap();
+        assert_eq!(found.cost, expected.cost);
+    }
 }
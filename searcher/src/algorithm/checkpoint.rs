@@ -0,0 +1,258 @@
+//! Saving a search part way through, so that it can be resumed later.
+//!
+//! With the `serde` feature, a [Checkpoint] can be serialized whenever the
+//! candidates, queue and cache can be, so that an expensive search can
+//! survive a restart, or be continued on another machine.
+
+use std::mem;
+
+use super::cache::{Cache, Keep};
+use super::observer::{CancelToken, Observers};
+use super::results::Results;
+use super::{SearchAlgorithm, SearchOptions, SearchQueue, SearchStats, StepLimit, TimeLimit};
+use crate::errors::{Result, SearchError};
+use crate::traits::SearchCandidate;
+
+/// The state of a search: the queue, the cache, the best result so far,
+/// any expansion left part way through, and the step and time counters.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checkpoint<S, Q, C> {
+    queue: Q,
+    cache: C,
+    results: Vec<S>,
+    expanding: Option<(S, usize)>,
+    steps: usize,
+    stats: SearchStats,
+}
+
+impl<S, Q, C> Checkpoint<S, Q, C> {
+    /// Steps taken before the checkpoint, which count towards the
+    /// step limit of the resumed search.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Statistics for the search up to the checkpoint.
+    pub fn stats(&self) -> &SearchStats {
+        &self.stats
+    }
+}
+
+impl<S, Q, C> SearchAlgorithm<S, Q, C>
+where
+    S: SearchCandidate + Ord,
+    Q: SearchQueue<Candidate = S> + Default,
+    C: Cache<Candidate = S>,
+{
+    /// Continue a search from a [Checkpoint].
+    pub fn resume(checkpoint: Checkpoint<S, Q, C>) -> Self {
+        Self::resume_with_options(checkpoint, SearchOptions::default())
+    }
+
+    /// Continue a search from a [Checkpoint], with new options.
+    ///
+    /// Step and time limits include the work done before the checkpoint.
+    pub fn resume_with_options(checkpoint: Checkpoint<S, Q, C>, options: SearchOptions) -> Self {
+        let mut search = SearchAlgorithm {
+            cache: checkpoint.cache,
+            queue: checkpoint.queue,
+            counter: StepLimit::resume(options.limit, checkpoint.steps),
+            timer: TimeLimit::resume(options.maxtime, checkpoint.stats.elapsed),
+            options,
+            origin: None,
+            stats: checkpoint.stats,
+            results: Results::default(),
+            observers: Observers::default(),
+            cancel: CancelToken::default(),
+            started: None,
+            expanding: checkpoint.expanding,
+            improved: false,
+        };
+        for result in checkpoint.results {
+            search.results.insert(result);
+        }
+        search
+    }
+
+    /// Run the search to completion, passing a [Checkpoint] to `save`
+    /// every `every` expansions.
+    ///
+    /// A checkpoint is also saved if the search stops early, e.g. at its
+    /// step limit or when cancelled, so that it can be resumed from there.
+    pub fn run_checkpointed<F>(mut self, every: usize, mut save: F) -> Result<S>
+    where
+        F: FnMut(&Checkpoint<S, Q, C>),
+    {
+        self.begin(Keep::Best(1))?;
+        loop {
+            match self.step() {
+                Ok(true) if self.stats.expanded % every.max(1) == 0 => self.save(&mut save),
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(error) => {
+                    self.save(&mut save);
+                    return Err(error);
+                }
+            }
+        }
+
+        let result = self.results.into_sorted().into_iter().next();
+        result.ok_or(SearchError::NoResultFound)
+    }
+
+    // Lend the state of the search out as a checkpoint, then take it back.
+    fn save<F>(&mut self, save: &mut F)
+    where
+        F: FnMut(&Checkpoint<S, Q, C>),
+    {
+        if let Some(start) = self.started {
+            self.stats.elapsed = start.elapsed();
+        }
+
+        let keep = self.results.keep();
+        let checkpoint = Checkpoint {
+            queue: mem::take(&mut self.queue),
+            cache: mem::take(&mut self.cache),
+            results: mem::take(&mut self.results).into_sorted(),
+            expanding: self.expanding.take(),
+            steps: self.counter.current,
+            stats: self.stats.clone(),
+        };
+        save(&checkpoint);
+
+        self.queue = checkpoint.queue;
+        self.cache = checkpoint.cache;
+        self.expanding = checkpoint.expanding;
+        self.results = Results::new(keep);
+        for result in checkpoint.results {
+            self.results.insert(result);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::dijkstra;
+    use crate::testing::LineWalker;
+    use crate::SearchScore;
+
+    impl SearchScore for LineWalker {
+        type Score = i32;
+
+        fn score(&self) -> i32 {
+            self.cost
+        }
+    }
+
+    fn origin() -> LineWalker {
+        LineWalker::new(8)
+    }
+
+    fn limited(steps: usize) -> SearchOptions {
+        SearchOptions {
+            limit: Some(steps),
+            ..SearchOptions::default()
+        }
+    }
+
+    #[test]
+    fn checkpointed() {
+        let expected = dijkstra::build(origin()).run_with_stats().unwrap();
+
+        let mut saved = Vec::new();
+        let found = dijkstra::build(origin())
+            .run_checkpointed(2, |c| saved.push(c.stats().expanded))
+            .unwrap();
+        assert_eq!(found.cost, expected.0.cost);
+        assert_eq!(saved.len(), expected.1.expanded / 2);
+        assert!(saved.iter().all(|e| e % 2 == 0));
+
+        let mut saved = None;
+        let result = dijkstra::build(origin())
+            .with_options(limited(10))
+            .run_checkpointed(100, |c| saved = Some(c.steps()));
+        assert!(matches!(result, Err(SearchError::StepLimitExhausted(10))));
+        assert_eq!(saved, Some(10));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn resume() {
+        let expected = dijkstra::run(origin()).unwrap();
+
+        let mut saved = String::new();
+        let result = dijkstra::build(origin())
+            .with_options(limited(10))
+            .run_checkpointed(100, |c| saved = serde_json::to_string(c).unwrap());
+        assert!(result.is_err());
+
+        let checkpoint: Checkpoint<LineWalker, _, _> = serde_json::from_str(&saved).unwrap();
+        assert_eq!(checkpoint.steps(), 10);
+        let found =
+            dijkstra::DijkstraSearch::<LineWalker>::resume_with_options(checkpoint, limited(1000))
+                .run()
+                .unwrap();
+        assert_eq!(found.cost, expected.cost);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn resume_part_way() {
+        // Some of these limits stop the search in the middle of expanding
+        // a candidate, the children already generated must not be
+        // generated again on resuming.
+        let expected = dijkstra::build(origin()).run_with_stats().unwrap().1;
+
+        let mut part_way = false;
+        for limit in 2..20 {
+            let mut saved = String::new();
+            let result = dijkstra::build(origin())
+                .with_options(limited(limit))
+                .run_checkpointed(100, |c| saved = serde_json::to_string(c).unwrap());
+            assert!(result.is_err());
+
+            let checkpoint: Checkpoint<LineWalker, _, _> = serde_json::from_str(&saved).unwrap();
+            part_way |= matches!(checkpoint.expanding, Some((_, i)) if i > 0);
+            let (_, stats) = dijkstra::DijkstraSearch::<LineWalker>::resume_with_options(
+                checkpoint,
+                limited(1000),
+            )
+            .run_with_stats()
+            .unwrap();
+            assert_eq!(stats.expanded, expected.expanded);
+            assert_eq!(stats.generated, expected.generated);
+            assert_eq!(stats.cache_hits, expected.cache_hits);
+        }
+        assert!(part_way);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn resume_scored() {
+        use crate::Score;
+
+        let expected = dijkstra::DijkstraSearch::<Score<LineWalker>>::new_with_score(origin())
+            .run()
+            .unwrap()
+            .unwrap();
+
+        let mut saved = String::new();
+        let result = dijkstra::DijkstraSearch::<Score<LineWalker>>::new_with_score(origin())
+            .with_options(limited(10))
+            .run_checkpointed(100, |c| saved = serde_json::to_string(c).unwrap());
+        assert!(result.is_err());
+
+        let checkpoint: Checkpoint<Score<LineWalker>, _, _> = serde_json::from_str(&saved).unwrap();
+        let found = dijkstra::DijkstraSearch::<Score<LineWalker>>::resume_with_options(
+            checkpoint,
+            limited(1000),
+        )
+        .run()
+        .unwrap()
+        .unwrap();
+        assert_eq!(found.position, expected.position);
+        assert_eq!(found.cost, expected.cost);
+    }
+}
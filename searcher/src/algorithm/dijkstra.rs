@@ -13,6 +13,7 @@ use crate::traits::{SearchCacher, SearchCandidate};
 /// A priority queue to always search the next shortest path
 /// by measured distance.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DijkstraQueue<S>
 where
     S: SearchCandidate + Ord,
@@ -49,9 +50,14 @@ where
         self.queue.len()
     }
 
-    #[allow(unused_variables)]
+    // Stop once nothing left in the queue could do better than the best
+    // result, since paths only get longer as they are extended.
     fn can_terminate(&self, candidate: &Self::Candidate) -> bool {
-        true
+        self.queue.peek().map(|c| c <= candidate).unwrap_or(true)
+    }
+
+    fn can_terminate_ties(&self, candidate: &Self::Candidate) -> bool {
+        self.queue.peek().map(|c| c < candidate).unwrap_or(true)
     }
 }
 
@@ -0,0 +1,252 @@
+//! Iterative deepening searches, which use memory proportional to the
+//! depth of the search rather than the size of its frontier.
+//!
+//! Each iteration is a depth-first search which gives up on candidates
+//! beyond a bound. When an iteration finds nothing, the bound is raised
+//! to the smallest value which was cut off, and the search starts again.
+
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::vec;
+
+use super::{SearchOptions, StepLimit, TimeLimit};
+use crate::errors::{Result, SearchError};
+use crate::traits::{SearchCandidate, SearchHeuristic};
+
+/// Measures how far a candidate is from the origin, to decide whether
+/// it falls within the bound of the current iteration.
+pub trait Deepening<S> {
+    type Bound: Debug + Ord + Clone;
+
+    fn bound(candidate: &S, depth: usize) -> Self::Bound;
+}
+
+/// Bounds candidates by their number of steps from the origin.
+#[derive(Debug)]
+pub struct Depth;
+
+impl<S> Deepening<S> for Depth {
+    type Bound = usize;
+
+    fn bound(_candidate: &S, depth: usize) -> Self::Bound {
+        depth
+    }
+}
+
+/// Bounds candidates by their estimated final score, from [SearchHeuristic].
+#[derive(Debug)]
+pub struct Estimate;
+
+impl<S> Deepening<S> for Estimate
+where
+    S: SearchHeuristic,
+    S::Hueristic: Clone,
+{
+    type Bound = S::Hueristic;
+
+    fn bound(candidate: &S, _depth: usize) -> Self::Bound {
+        candidate.heuristic()
+    }
+}
+
+enum Deepened<S, B> {
+    Found(S),
+    Exceeded(B),
+    Exhausted,
+}
+
+/// Implementation of iterative deepening, with the bound (D)
+/// chosen by the algorithm.
+#[derive(Debug)]
+pub struct IterativeSearch<S, D>
+where
+    S: SearchCandidate,
+    D: Deepening<S>,
+{
+    counter: StepLimit,
+    timer: TimeLimit,
+    options: SearchOptions,
+    origin: Option<S>,
+    deepening: PhantomData<D>,
+}
+
+impl<S, D> IterativeSearch<S, D>
+where
+    S: SearchCandidate,
+    D: Deepening<S>,
+{
+    pub fn new_with_options(origin: S, options: SearchOptions) -> Self {
+        IterativeSearch {
+            counter: StepLimit::new(options.limit),
+            timer: TimeLimit::new(options.maxtime),
+            options,
+            origin: Some(origin),
+            deepening: PhantomData,
+        }
+    }
+
+    pub fn new(origin: S) -> Self {
+        Self::new_with_options(origin, SearchOptions::default())
+    }
+
+    pub fn with_options(mut self, options: SearchOptions) -> Self {
+        let origin = self
+            .origin
+            .take()
+            .expect("Algorithm appears to have already started, no origin!");
+
+        Self::new_with_options(origin, options)
+    }
+
+    fn step(&mut self) -> Result<()> {
+        self.counter.increment()?;
+        self.timer.increment()
+    }
+
+    // Depth-first search within the bound. Each level of the stack holds
+    // the children of one candidate which are still to be visited.
+    fn deepen(&mut self, origin: &S, bound: &D::Bound) -> Result<Deepened<S, D::Bound>> {
+        let mut next: Option<D::Bound> = None;
+        let mut stack: Vec<vec::IntoIter<S>> = vec![origin.children().into_iter()];
+
+        while let Some(level) = stack.last_mut() {
+            let candidate = match level.next() {
+                Some(c) => c,
+                None => {
+                    stack.pop();
+                    continue;
+                }
+            };
+            self.step()?;
+
+            let measure = D::bound(&candidate, stack.len());
+            if &measure > bound {
+                if next.as_ref().map(|n| &measure < n).unwrap_or(true) {
+                    next = Some(measure);
+                }
+                continue;
+            }
+
+            if candidate.is_complete() {
+                return Ok(Deepened::Found(candidate));
+            }
+            stack.push(candidate.children().into_iter());
+        }
+
+        Ok(next.map(Deepened::Exceeded).unwrap_or(Deepened::Exhausted))
+    }
+
+    /// Run the search to completion.
+    pub fn run(mut self) -> Result<S> {
+        let origin = self.origin.take().unwrap();
+
+        self.step()?;
+        if origin.is_complete() {
+            return Ok(origin);
+        }
+
+        let mut bound = D::bound(&origin, 0);
+        let mut n = 0;
+        loop {
+            n += 1;
+            if self.options.verbose.map(|v| n % v == 0).unwrap_or(false) {
+                eprintln!("I{} B{:?} {}", n, bound, self.counter.current);
+            }
+
+            match self.deepen(&origin, &bound)? {
+                Deepened::Found(candidate) => return Ok(candidate),
+                Deepened::Exceeded(next) => bound = next,
+                Deepened::Exhausted => return Err(SearchError::NoResultFound),
+            }
+        }
+    }
+}
+
+pub mod idastar {
+    //! Iterative-deepening A*
+
+    use super::{Estimate, IterativeSearch};
+    use crate::errors::Result;
+    use crate::traits::{SearchCandidate, SearchHeuristic};
+
+    pub type IDAStarSearcher<S> = IterativeSearch<S, Estimate>;
+
+    pub fn build<S>(origin: S) -> IDAStarSearcher<S>
+    where
+        S: SearchHeuristic + SearchCandidate,
+        S::Hueristic: Clone,
+    {
+        IterativeSearch::new(origin)
+    }
+
+    /// Perform a search using iterative-deepening A*.
+    ///
+    /// Like A*, the first result found is the best one as long as the
+    /// heuristic never overestimates. Candidates aren't cached, so states
+    /// may be revisited, but only the current path is kept in memory.
+    pub fn run<S>(origin: S) -> Result<S>
+    where
+        S: SearchHeuristic + SearchCandidate,
+        S::Hueristic: Clone,
+    {
+        build(origin).run()
+    }
+}
+
+pub mod iddfs {
+    //! Iterative-deepening depth-first search
+
+    use super::{Depth, IterativeSearch};
+    use crate::errors::Result;
+    use crate::traits::SearchCandidate;
+
+    pub type IDDFSearcher<S> = IterativeSearch<S, Depth>;
+
+    pub fn build<S>(origin: S) -> IDDFSearcher<S>
+    where
+        S: SearchCandidate,
+    {
+        IterativeSearch::new(origin)
+    }
+
+    /// Perform a depth-first search which is repeated with an increasing
+    /// depth limit, so that the result found has the fewest steps.
+    pub fn run<S>(origin: S) -> Result<S>
+    where
+        S: SearchCandidate,
+    {
+        build(origin).run()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::LineWalker;
+
+    #[test]
+    fn idastar_cheapest() {
+        let found = idastar::run(LineWalker::new(7)).unwrap();
+        assert_eq!(found.cost, 7);
+    }
+
+    #[test]
+    fn iddfs_fewest_steps() {
+        // Three moves, even though five single steps would be cheaper.
+        let found = iddfs::run(LineWalker::new(5)).unwrap();
+        assert_eq!(found.cost, 6);
+    }
+
+    #[test]
+    fn step_limit() {
+        let options = SearchOptions {
+            limit: Some(10),
+            ..SearchOptions::default()
+        };
+
+        let result = iddfs::build(LineWalker::new(100))
+            .with_options(options)
+            .run();
+        assert!(matches!(result, Err(SearchError::StepLimitExhausted(10))));
+    }
+}
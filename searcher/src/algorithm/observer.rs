@@ -0,0 +1,103 @@
+//! Hooks for watching and stopping a search while it runs.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::errors::{Result, SearchError};
+
+/// Something which happened to a candidate during a search.
+#[derive(Debug)]
+pub enum SearchEvent<'c, S> {
+    /// The candidate was taken from the queue and its children generated.
+    Expanded(&'c S),
+
+    /// The candidate passed the cache and was added to the queue.
+    Pushed(&'c S),
+
+    /// The candidate is complete, and so is a possible result.
+    Completed(&'c S),
+}
+
+// Derived impls would require S: Copy.
+impl<'c, S> Clone for SearchEvent<'c, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'c, S> Copy for SearchEvent<'c, S> {}
+
+/// Receives a [SearchEvent] for each step of a search, e.g. to
+/// drive a progress bar.
+///
+/// Implemented for closures which take a [SearchEvent].
+pub trait SearchObserver<S> {
+    fn observe(&mut self, event: SearchEvent<'_, S>);
+}
+
+impl<S, F> SearchObserver<S> for F
+where
+    F: FnMut(SearchEvent<'_, S>),
+{
+    fn observe(&mut self, event: SearchEvent<'_, S>) {
+        self(event)
+    }
+}
+
+// Observers are Send, so that searches can still run on another thread.
+pub(crate) struct Observers<S>(Vec<Box<dyn SearchObserver<S> + Send>>);
+
+impl<S> Observers<S> {
+    pub(crate) fn push(&mut self, observer: Box<dyn SearchObserver<S> + Send>) {
+        self.0.push(observer);
+    }
+
+    pub(crate) fn notify(&mut self, event: SearchEvent<'_, S>) {
+        for observer in self.0.iter_mut() {
+            observer.observe(event);
+        }
+    }
+}
+
+impl<S> Default for Observers<S> {
+    fn default() -> Self {
+        Observers(Vec::new())
+    }
+}
+
+impl<S> fmt::Debug for Observers<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Observers({})", self.0.len())
+    }
+}
+
+/// A flag which stops a search from another thread.
+///
+/// Clones share the same flag, so keep one and hand a clone to the
+/// search. Once cancelled, the search returns [SearchError::Cancelled]
+/// at its next step.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(SearchError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,478 @@
+//! Multi-threaded searches
+//!
+//! Worker threads share a single frontier, held in one of the usual search
+//! queues behind a lock. Each worker takes the best candidate from the
+//! queue, expands it without holding the lock, and returns the children it
+//! kept. Explored states are shared through a [ConcurrentCache].
+//!
+//! This pays off when expanding a candidate is expensive compared to the
+//! cost of a lock, which is the case for most puzzle searches.
+
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time;
+
+use super::cache::ConcurrentCache;
+use super::observer::CancelToken;
+use super::{SearchOptions, SearchQueue, SearchStats};
+use crate::errors::{Result, SearchError};
+use crate::traits::{SearchCacher, SearchCandidate};
+
+// Everything the workers need to agree on, guarded by one lock.
+#[derive(Debug)]
+struct Frontier<S, Q> {
+    queue: Q,
+    best: Option<S>,
+
+    // Workers which are expanding a candidate, and so may yet
+    // add to the queue.
+    active: usize,
+    error: Option<SearchError>,
+    done: bool,
+    peak: usize,
+}
+
+#[derive(Debug)]
+struct Shared<S, Q>
+where
+    S: SearchCacher,
+{
+    frontier: Mutex<Frontier<S, Q>>,
+    ready: Condvar,
+    cache: ConcurrentCache<S>,
+    steps: AtomicUsize,
+    start: time::Instant,
+    options: SearchOptions,
+    cancel: CancelToken,
+}
+
+impl<S, Q> Shared<S, Q>
+where
+    S: SearchCandidate + SearchCacher + Ord,
+    Q: SearchQueue<Candidate = S>,
+{
+    fn lock(&self) -> MutexGuard<'_, Frontier<S, Q>> {
+        self.frontier
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn stop(&self, frontier: &mut Frontier<S, Q>, error: Option<SearchError>) {
+        if frontier.error.is_none() {
+            frontier.error = error;
+        }
+        frontier.done = true;
+        self.ready.notify_all();
+    }
+
+    fn step(&self) -> Result<()> {
+        let n = self.steps.fetch_add(1, Ordering::Relaxed) + 1;
+
+        if self.options.limit.map(|v| n >= v).unwrap_or(false) {
+            return Err(SearchError::StepLimitExhausted(n));
+        }
+        if self
+            .options
+            .maxtime
+            .map(|m| self.start.elapsed() > m)
+            .unwrap_or(false)
+        {
+            return Err(SearchError::TimeLimitExhausted(self.start.elapsed()));
+        }
+        self.cancel.check()
+    }
+
+    // Wait for the next candidate worth expanding, or None once the
+    // search is over.
+    fn next(&self) -> Option<S> {
+        let mut frontier = self.lock();
+        loop {
+            if frontier.done {
+                return None;
+            }
+
+            // Once nothing queued can beat the best result, the queue only
+            // matters again if an active worker finds something better.
+            let exhausted = !self.options.exhaustive
+                && frontier
+                    .best
+                    .as_ref()
+                    .map(|b| frontier.queue.can_terminate(b))
+                    .unwrap_or(false);
+
+            if !exhausted {
+                if let Some(candidate) = frontier.queue.pop() {
+                    frontier.active += 1;
+                    return Some(candidate);
+                }
+            }
+
+            if frontier.active == 0 {
+                self.stop(&mut frontier, None);
+                return None;
+            }
+
+            frontier = self
+                .ready
+                .wait(frontier)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    // Should we continue searching from this candidate? The best result
+    // is a copy taken by the worker, so that the frontier is only locked
+    // when a complete candidate is found.
+    fn process_candidate(
+        &self,
+        candidate: S,
+        best: &mut Option<S>,
+        stats: &mut SearchStats,
+    ) -> Result<Option<S>> {
+        self.step()?;
+        stats.generated += 1;
+
+        if candidate.is_complete() {
+            stats.complete += 1;
+            let mut frontier = self.lock();
+            if frontier
+                .best
+                .as_ref()
+                .map(|b| &candidate > b)
+                .unwrap_or(true)
+            {
+                frontier.best = Some(candidate);
+            }
+            *best = frontier.best.clone();
+            return Ok(None);
+        }
+
+        // The copy of the best result may be out of date, which only
+        // costs some wasted work.
+        if best.as_ref().map(|b| &candidate <= b).unwrap_or(false) {
+            return Ok(None);
+        }
+
+        if self.cache.check(&candidate)? {
+            stats.cache_misses += 1;
+            return Ok(Some(candidate));
+        }
+        stats.cache_hits += 1;
+        Ok(None)
+    }
+
+    fn expand(&self, candidate: S, stats: &mut SearchStats) -> Result<Vec<S>> {
+        stats.expanded += 1;
+        let mut best = self.lock().best.clone();
+        let mut kept = Vec::new();
+        for child in candidate.children() {
+            if let Some(c) = self.process_candidate(child, &mut best, stats)? {
+                kept.push(c);
+            }
+        }
+        Ok(kept)
+    }
+
+    // Work until the search is over, returning the statistics
+    // for this worker alone.
+    fn work(&self) -> SearchStats {
+        let mut stats = SearchStats::default();
+        while let Some(candidate) = self.next() {
+            let expanded = self.expand(candidate, &mut stats);
+
+            let mut frontier = self.lock();
+            frontier.active -= 1;
+            match expanded {
+                Ok(children) => {
+                    for child in children {
+                        frontier.queue.push(child);
+                    }
+                    frontier.peak = frontier.peak.max(frontier.queue.len());
+                    self.ready.notify_all();
+                }
+                Err(error) => self.stop(&mut frontier, Some(error)),
+            }
+
+            let n = self.steps.load(Ordering::Relaxed);
+            if self.options.verbose.map(|v| n % v == 0).unwrap_or(false) {
+                eprintln!("Q{} A{} {}", frontier.queue.len(), frontier.active, n);
+            }
+        }
+        stats
+    }
+}
+
+/// Search which expands candidates on several threads at once, using
+/// the queue (Q) to decide which candidate comes next.
+#[derive(Debug)]
+pub struct ParallelSearch<S, Q>
+where
+    S: SearchCandidate + SearchCacher + Ord + Send,
+    Q: SearchQueue<Candidate = S> + Default + Send,
+{
+    threads: usize,
+    options: SearchOptions,
+    cancel: CancelToken,
+    origin: Option<S>,
+    queue: PhantomData<Q>,
+}
+
+impl<S, Q> ParallelSearch<S, Q>
+where
+    S: SearchCandidate + SearchCacher + Ord + Send,
+    S::State: Send,
+    S::Value: Send,
+    Q: SearchQueue<Candidate = S> + Default + Send,
+{
+    pub fn new_with_options(origin: S, options: SearchOptions) -> Self {
+        ParallelSearch {
+            threads: thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+            options,
+            cancel: CancelToken::default(),
+            origin: Some(origin),
+            queue: PhantomData,
+        }
+    }
+
+    pub fn new(origin: S) -> Self {
+        Self::new_with_options(origin, SearchOptions::default())
+    }
+
+    pub fn with_options(mut self, options: SearchOptions) -> Self {
+        let origin = self
+            .origin
+            .take()
+            .expect("Algorithm appears to have already started, no origin!");
+
+        Self::new_with_options(origin, options)
+            .with_threads(self.threads)
+            .with_cancellation(self.cancel)
+    }
+
+    /// Set the number of worker threads, which defaults to the
+    /// available parallelism of the machine.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Stop every worker with [SearchError::Cancelled] once the token
+    /// is cancelled.
+    pub fn with_cancellation(mut self, token: CancelToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Run the search to completion.
+    pub fn run(self) -> Result<S> {
+        self.run_with_stats().map(|(result, _)| result)
+    }
+
+    /// Run the search to completion, returning the result along with
+    /// statistics summed over all of the workers.
+    pub fn run_with_stats(mut self) -> Result<(S, SearchStats)> {
+        let origin = self.origin.take().unwrap();
+        let threads = self.threads;
+
+        let shared = Shared {
+            frontier: Mutex::new(Frontier {
+                queue: Q::default(),
+                best: None,
+                active: 0,
+                error: None,
+                done: false,
+                peak: 0,
+            }),
+            ready: Condvar::new(),
+            cache: ConcurrentCache::default(),
+            steps: AtomicUsize::new(0),
+            start: time::Instant::now(),
+            options: self.options,
+            cancel: self.cancel,
+        };
+
+        let mut stats = SearchStats::default();
+        if let Some(c) = shared.process_candidate(origin, &mut None, &mut stats)? {
+            let mut frontier = shared.lock();
+            frontier.queue.push(c);
+            frontier.peak = 1;
+        }
+
+        let workers: Vec<SearchStats> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|_| scope.spawn(|| shared.work()))
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("Search worker panicked"))
+                .collect()
+        });
+
+        for worker in workers {
+            stats.expanded += worker.expanded;
+            stats.generated += worker.generated;
+            stats.cache_hits += worker.cache_hits;
+            stats.cache_misses += worker.cache_misses;
+            stats.complete += worker.complete;
+        }
+        stats.elapsed = shared.start.elapsed();
+
+        let frontier = shared
+            .frontier
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        stats.peak_queue = frontier.peak;
+
+        match frontier.error {
+            Some(error) => Err(error),
+            None => frontier
+                .best
+                .map(|best| (best, stats))
+                .ok_or(SearchError::NoResultFound),
+        }
+    }
+}
+
+pub mod dijkstra {
+    //! Dijkstra's algorithm on several threads
+
+    use super::ParallelSearch;
+    use crate::algorithm::dijkstra::DijkstraQueue;
+    use crate::errors::Result;
+    use crate::traits::{SearchCacher, SearchCandidate};
+
+    pub type ParallelDijkstraSearch<S> = ParallelSearch<S, DijkstraQueue<S>>;
+
+    pub fn build<S>(origin: S) -> ParallelDijkstraSearch<S>
+    where
+        S: SearchCandidate + SearchCacher + Ord + Send,
+        S::State: Send,
+        S::Value: Send,
+    {
+        ParallelSearch::new(origin)
+    }
+
+    /// Perform a search using Dijkstra's algorithm, expanding candidates
+    /// on every available core. The result is the same as for
+    /// [crate::dijkstra::run], though ties may be broken differently.
+    pub fn run<S>(origin: S) -> Result<S>
+    where
+        S: SearchCandidate + SearchCacher + Ord + Send,
+        S::State: Send,
+        S::Value: Send,
+    {
+        build(origin).run()
+    }
+}
+
+pub mod astar {
+    //! A* on several threads
+
+    use super::ParallelSearch;
+    use crate::algorithm::astar::AStarQueue;
+    use crate::errors::Result;
+    use crate::traits::{SearchCacher, SearchCandidate, SearchHeuristic};
+
+    pub type ParallelAStarSearch<S> = ParallelSearch<S, AStarQueue<S>>;
+
+    pub fn build<S>(origin: S) -> ParallelAStarSearch<S>
+    where
+        S: SearchHeuristic + SearchCandidate + SearchCacher + Ord + Send,
+        S::Hueristic: Send,
+        S::State: Send,
+        S::Value: Send,
+    {
+        ParallelSearch::new(origin)
+    }
+
+    /// Perform an A* search, expanding candidates on every available core.
+    pub fn run<S>(origin: S) -> Result<S>
+    where
+        S: SearchHeuristic + SearchCandidate + SearchCacher + Ord + Send,
+        S::Hueristic: Send,
+        S::State: Send,
+        S::Value: Send,
+    {
+        build(origin).run()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::GridWalker;
+
+    #[test]
+    fn matches_serial() {
+        let expected = crate::dijkstra::run(GridWalker::new(12)).unwrap();
+
+        for threads in 1..=4 {
+            let found = dijkstra::build(GridWalker::new(12))
+                .with_threads(threads)
+                .run()
+                .unwrap();
+            assert_eq!(found.steps, expected.steps);
+
+            let found = astar::build(GridWalker::new(12))
+                .with_threads(threads)
+                .run()
+                .unwrap();
+            assert_eq!(found.steps, expected.steps);
+        }
+    }
+
+    #[test]
+    fn stats() {
+        let serial = crate::dijkstra::run(GridWalker::new(12)).unwrap();
+        let (found, stats) = dijkstra::build(GridWalker::new(12))
+            .with_threads(3)
+            .run_with_stats()
+            .unwrap();
+        assert_eq!(found.steps, serial.steps);
+
+        // Counts are summed over the workers.
+        assert!(stats.expanded > 0);
+        assert!(stats.generated >= stats.expanded);
+        assert!(stats.cache_hits + stats.cache_misses <= stats.generated);
+        assert!(stats.complete >= 1);
+        assert!(stats.peak_queue > 0);
+    }
+
+    #[test]
+    fn step_limit() {
+        let options = SearchOptions {
+            limit: Some(10),
+            ..SearchOptions::default()
+        };
+
+        let result = dijkstra::build(GridWalker::new(12))
+            .with_options(options)
+            .with_threads(4)
+            .run();
+        assert!(matches!(result, Err(SearchError::StepLimitExhausted(_))));
+    }
+
+    #[test]
+    fn cancelled() {
+        let token = CancelToken::new();
+        token.cancel();
+
+        let result = dijkstra::build(GridWalker::new(12))
+            .with_cancellation(token)
+            .run();
+        assert!(matches!(result, Err(SearchError::Cancelled)));
+    }
+
+    #[test]
+    fn cache() {
+        let cache = ConcurrentCache::new(4);
+        let walker = GridWalker::new(2);
+        assert!(cache.check(&walker).unwrap());
+        assert!(!cache.check(&walker).unwrap());
+        assert_eq!(cache.len(), 1);
+    }
+}
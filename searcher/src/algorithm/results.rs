@@ -0,0 +1,150 @@
+//! Complete candidates collected by a search.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use super::cache::Keep;
+use super::SearchQueue;
+
+#[derive(Debug)]
+pub(crate) struct Results<S>
+where
+    S: Ord,
+{
+    keep: Keep,
+
+    // The worst result kept is at the top, ready to be replaced.
+    heap: BinaryHeap<Reverse<S>>,
+}
+
+impl<S> Default for Results<S>
+where
+    S: Ord,
+{
+    fn default() -> Self {
+        Self::new(Keep::Best(1))
+    }
+}
+
+impl<S> Results<S>
+where
+    S: Ord,
+{
+    pub(crate) fn new(keep: Keep) -> Self {
+        let keep = match keep {
+            Keep::Best(k) => Keep::Best(k.max(1)),
+            Keep::Ties => Keep::Ties,
+        };
+        Results {
+            keep,
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    pub(crate) fn keep(&self) -> Keep {
+        self.keep
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub(crate) fn best(&self) -> Option<&S> {
+        self.heap.iter().map(|Reverse(c)| c).max()
+    }
+
+    fn worst(&self) -> Option<&S> {
+        self.heap.peek().map(|Reverse(c)| c)
+    }
+
+    // The result which any new result has to beat (or tie with, for
+    // [Keep::Ties]) to be kept, once there are enough results.
+    fn bound(&self) -> Option<&S> {
+        match self.keep {
+            Keep::Best(k) if self.heap.len() < k => None,
+            _ => self.worst(),
+        }
+    }
+
+    pub(crate) fn insert(&mut self, candidate: S) {
+        match self.keep {
+            Keep::Best(k) => {
+                self.heap.push(Reverse(candidate));
+                if self.heap.len() > k {
+                    self.heap.pop();
+                }
+            }
+            Keep::Ties => match self.worst().map(|w| candidate.cmp(w)) {
+                Some(std::cmp::Ordering::Less) => {}
+                Some(std::cmp::Ordering::Equal) => self.heap.push(Reverse(candidate)),
+                _ => {
+                    self.heap.clear();
+                    self.heap.push(Reverse(candidate));
+                }
+            },
+        }
+    }
+
+    /// Can this candidate be dropped, since it can't improve the results?
+    pub(crate) fn prunes(&self, candidate: &S) -> bool {
+        match (self.keep, self.bound()) {
+            (Keep::Ties, Some(b)) => candidate < b,
+            (_, Some(b)) => candidate <= b,
+            (_, None) => false,
+        }
+    }
+
+    pub(crate) fn can_terminate<Q>(&self, queue: &Q) -> bool
+    where
+        Q: SearchQueue<Candidate = S>,
+    {
+        match (self.keep, self.bound()) {
+            (Keep::Ties, Some(b)) => queue.can_terminate_ties(b),
+            (_, Some(b)) => queue.can_terminate(b),
+            (_, None) => false,
+        }
+    }
+
+    /// The results, best first.
+    pub(crate) fn into_sorted(self) -> Vec<S> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(c)| c)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::GridWalker;
+    use crate::{astar, dijkstra};
+
+    #[test]
+    fn all_optimal() {
+        let mut routes: Vec<_> = dijkstra::build(GridWalker::new(2))
+            .run_all_optimal()
+            .unwrap()
+            .map(|w| {
+                assert_eq!(w.steps, 4);
+                w.route
+            })
+            .collect();
+        routes.sort();
+        routes.dedup();
+        assert_eq!(routes.len(), 6);
+
+        let found = astar::build(GridWalker::new(2)).run_all_optimal().unwrap();
+        assert_eq!(found.count(), 6);
+    }
+
+    #[test]
+    fn k_best() {
+        let steps: Vec<_> = dijkstra::build(GridWalker::new(2))
+            .run_k_best(8)
+            .unwrap()
+            .map(|w| w.steps)
+            .collect();
+        assert_eq!(steps, vec![4, 4, 4, 4, 4, 4, 6, 6]);
+    }
+}
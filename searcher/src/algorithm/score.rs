@@ -4,6 +4,14 @@ use std::convert::From;
 use crate::traits::{SearchCacher, SearchCandidate, SearchScore, SearchState};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "S: serde::Serialize, S::Score: serde::Serialize",
+        deserialize = "S: serde::Deserialize<'de>, S::Score: serde::Deserialize<'de>"
+    ))
+)]
 pub struct Score<S>
 where
     S: SearchCandidate + SearchScore,
@@ -0,0 +1,378 @@
+//! Simplified Memory-bounded A* (SMA*)
+//!
+//! Like A*, but the search tree is never allowed to grow beyond a fixed
+//! number of candidates. When it is full, the worst leaf is dropped, and
+//! its estimate is backed up into its parent, so that the parent can be
+//! expanded again later if the dropped branch turns out to be the best.
+//!
+//! With enough memory to hold the path to the best result, the result
+//! is the same as for A*. With less, no result is found.
+
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+
+use super::{SearchOptions, StepLimit, TimeLimit};
+use crate::errors::{Result, SearchError};
+use crate::traits::{SearchCandidate, SearchHeuristic};
+
+/// Memory used by [build], in candidates.
+pub const DEFAULT_MEMORY: usize = 1 << 16;
+
+// An estimate, which is infinite for branches known to lead nowhere.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Estimate<H> {
+    Finite(H),
+    Infinite,
+}
+
+#[derive(Debug)]
+struct Node<S>
+where
+    S: SearchHeuristic,
+{
+    candidate: S,
+    estimate: Estimate<S::Hueristic>,
+    parent: Option<usize>,
+
+    // Position of this candidate among its parent's children.
+    index: usize,
+    depth: usize,
+    expanded: bool,
+
+    // Children currently held in the tree.
+    live: usize,
+
+    // Children which were dropped, by position, with their estimates.
+    forgotten: Vec<(usize, Estimate<S::Hueristic>)>,
+}
+
+impl<S> Node<S>
+where
+    S: SearchHeuristic,
+    S::Hueristic: Clone,
+{
+    // The best estimate for anything still to be explored from this node.
+    fn value(&self) -> Estimate<S::Hueristic> {
+        self.forgotten
+            .iter()
+            .map(|(_, e)| e.clone())
+            .min()
+            .unwrap_or_else(|| self.estimate.clone())
+    }
+
+    // Nodes are open until they are expanded, and again whenever
+    // any of their children have been dropped.
+    fn is_open(&self) -> bool {
+        !self.expanded || !self.forgotten.is_empty()
+    }
+}
+
+// Open nodes ordered by value, with deeper nodes first among ties.
+type Open<H> = (Estimate<H>, Reverse<usize>, usize);
+
+/// Memory bounded search, see [run].
+#[derive(Debug)]
+pub struct SMAStarSearch<S>
+where
+    S: SearchHeuristic,
+{
+    counter: StepLimit,
+    timer: TimeLimit,
+    options: SearchOptions,
+    memory: usize,
+    origin: Option<S>,
+
+    nodes: Vec<Option<Node<S>>>,
+    free: Vec<usize>,
+    open: BTreeSet<Open<S::Hueristic>>,
+}
+
+impl<S> SMAStarSearch<S>
+where
+    S: SearchHeuristic + SearchCandidate,
+    S::Hueristic: Clone,
+{
+    pub fn new_with_options(origin: S, memory: usize, options: SearchOptions) -> Self {
+        SMAStarSearch {
+            counter: StepLimit::new(options.limit),
+            timer: TimeLimit::new(options.maxtime),
+            options,
+            memory: memory.max(2),
+            origin: Some(origin),
+            nodes: Vec::new(),
+            free: Vec::new(),
+            open: BTreeSet::new(),
+        }
+    }
+
+    pub fn new(origin: S, memory: usize) -> Self {
+        Self::new_with_options(origin, memory, SearchOptions::default())
+    }
+
+    pub fn with_options(mut self, options: SearchOptions) -> Self {
+        let origin = self
+            .origin
+            .take()
+            .expect("Algorithm appears to have already started, no origin!");
+
+        Self::new_with_options(origin, self.memory, options)
+    }
+
+    fn node(&self, id: usize) -> &Node<S> {
+        self.nodes[id].as_ref().expect("Node was dropped")
+    }
+
+    fn len(&self) -> usize {
+        self.nodes.len() - self.free.len()
+    }
+
+    // Change a node, keeping its place in the open set up to date.
+    fn update<F>(&mut self, id: usize, change: F)
+    where
+        F: FnOnce(&mut Node<S>),
+    {
+        let node = self.nodes[id].as_mut().expect("Node was dropped");
+        if node.is_open() {
+            self.open.remove(&(node.value(), Reverse(node.depth), id));
+        }
+        change(node);
+        if node.is_open() {
+            self.open.insert((node.value(), Reverse(node.depth), id));
+        }
+    }
+
+    fn insert(&mut self, node: Node<S>) -> usize {
+        let key = (node.value(), Reverse(node.depth));
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.nodes[id] = Some(node);
+                id
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        self.open.insert((key.0, key.1, id));
+        id
+    }
+
+    // Drop a node with no children in the tree, backing its value up
+    // into its parent so that it can be regenerated later.
+    fn forget(&mut self, id: usize) {
+        let node = self.nodes[id].take().expect("Node was dropped");
+        if node.is_open() {
+            self.open.remove(&(node.value(), Reverse(node.depth), id));
+        }
+        self.free.push(id);
+
+        if let Some(parent) = node.parent {
+            let value = node.value();
+            self.update(parent, |p| {
+                p.live -= 1;
+                p.forgotten.push((node.index, value));
+            });
+        }
+    }
+
+    // The worst node which can be dropped: one without children in the
+    // tree, other than the origin.
+    fn worst(&self) -> Option<usize> {
+        self.open.iter().rev().map(|&(_, _, id)| id).find(|&id| {
+            let node = self.node(id);
+            node.live == 0 && node.parent.is_some()
+        })
+    }
+
+    fn expand(&mut self, id: usize) {
+        let node = self.node(id);
+        let (estimate, depth) = (node.estimate.clone(), node.depth);
+        let mut children: Vec<_> = node.candidate.children().into_iter().map(Some).collect();
+
+        // Regenerate only the dropped children of a node which has been
+        // expanded before, keeping their backed up estimates.
+        let targets: Vec<(usize, Option<Estimate<S::Hueristic>>)> = if node.expanded {
+            node.forgotten
+                .iter()
+                .map(|(i, e)| (*i, Some(e.clone())))
+                .collect()
+        } else {
+            (0..children.len()).map(|i| (i, None)).collect()
+        };
+
+        if !node.expanded && children.is_empty() {
+            self.update(id, |n| {
+                n.expanded = true;
+                n.estimate = Estimate::Infinite;
+            });
+            self.forget(id);
+            return;
+        }
+
+        self.update(id, |n| {
+            n.expanded = true;
+            n.forgotten.clear();
+            n.live += targets.len();
+        });
+
+        for (index, backed_up) in targets {
+            let child = children[index]
+                .take()
+                .expect("Children of a candidate must be the same each time");
+
+            // A child which can't be extended within the memory limit
+            // is a dead end, unless it is already complete.
+            let child_estimate = if depth + 2 >= self.memory && !child.is_complete() {
+                Estimate::Infinite
+            } else {
+                // Estimates never decrease along a path.
+                backed_up
+                    .unwrap_or_else(|| Estimate::Finite(child.heuristic()).max(estimate.clone()))
+            };
+
+            self.insert(Node {
+                candidate: child,
+                estimate: child_estimate,
+                parent: Some(id),
+                index,
+                depth: depth + 1,
+                expanded: false,
+                live: 0,
+                forgotten: Vec::new(),
+            });
+        }
+
+        while self.len() > self.memory {
+            match self.worst() {
+                Some(worst) => self.forget(worst),
+                None => break,
+            }
+        }
+    }
+
+    /// Run the search to completion.
+    pub fn run(mut self) -> Result<S> {
+        let origin = self.origin.take().unwrap();
+        let estimate = Estimate::Finite(origin.heuristic());
+        self.insert(Node {
+            candidate: origin,
+            estimate,
+            parent: None,
+            index: 0,
+            depth: 0,
+            expanded: false,
+            live: 0,
+            forgotten: Vec::new(),
+        });
+
+        let mut n = 0;
+        loop {
+            let (value, id) = match self.open.iter().next() {
+                Some((v, _, id)) => (v.clone(), *id),
+                None => return Err(SearchError::NoResultFound),
+            };
+            if value == Estimate::Infinite {
+                return Err(SearchError::NoResultFound);
+            }
+
+            self.counter.increment()?;
+            self.timer.increment()?;
+
+            n += 1;
+            if self.options.verbose.map(|v| n % v == 0).unwrap_or(false) {
+                eprintln!("O{} N{} {}", self.open.len(), self.len(), n);
+            }
+
+            if self.node(id).candidate.is_complete() {
+                let node = self.nodes[id].take().unwrap();
+                return Ok(node.candidate);
+            }
+
+            self.expand(id);
+        }
+    }
+}
+
+pub fn build<S>(origin: S) -> SMAStarSearch<S>
+where
+    S: SearchHeuristic + SearchCandidate,
+    S::Hueristic: Clone,
+{
+    SMAStarSearch::new(origin, DEFAULT_MEMORY)
+}
+
+/// Perform an SMA* search, holding at most `memory` candidates at once.
+///
+/// As with A*, the heuristic must never overestimate for the result to be
+/// the best one. Candidates aren't cached, so states may be revisited.
+pub fn run<S>(origin: S, memory: usize) -> Result<S>
+where
+    S: SearchHeuristic + SearchCandidate,
+    S::Hueristic: Clone,
+{
+    SMAStarSearch::new(origin, memory).run()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar, SearchCacher, SearchOrd, SearchState};
+
+    // Walks across a grid to the far corner, around a wall along x = 3
+    // with a gap at the bottom.
+    #[derive(Debug, Clone, SearchOrd, SearchState, SearchCacher)]
+    struct Walker {
+        #[search(state)]
+        position: (i32, i32),
+        #[search(cost)]
+        steps: i32,
+    }
+
+    impl SearchCandidate for Walker {
+        fn is_complete(&self) -> bool {
+            self.position == (5, 5)
+        }
+
+        fn children(&self) -> Vec<Self> {
+            let (x, y) = self.position;
+            [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
+                .iter()
+                .filter(|(x, y)| (0..=5).contains(x) && (0..=5).contains(y))
+                .filter(|&&(x, y)| x != 3 || y == 0)
+                .map(|&position| Walker {
+                    position,
+                    steps: self.steps + 1,
+                })
+                .collect()
+        }
+    }
+
+    impl SearchHeuristic for Walker {
+        type Hueristic = i32;
+
+        fn heuristic(&self) -> i32 {
+            self.steps + (5 - self.position.0) + (5 - self.position.1)
+        }
+    }
+
+    fn origin() -> Walker {
+        Walker {
+            position: (0, 5),
+            steps: 0,
+        }
+    }
+
+    #[test]
+    fn matches_astar() {
+        let expected = astar::run(origin()).unwrap();
+        let found = run(origin(), 40).unwrap();
+        assert_eq!(found.steps, expected.steps);
+    }
+
+    #[test]
+    fn memory_too_small() {
+        // The shortest path has 16 steps, which can't fit in 10 nodes.
+        let result = run(origin(), 10);
+        assert!(matches!(result, Err(SearchError::NoResultFound)));
+    }
+}
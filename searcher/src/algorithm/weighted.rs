@@ -0,0 +1,199 @@
+//! Weighted A* Search Algorithm
+//!
+//! Like A*, but the estimate of the remaining cost is inflated by a weight
+//! greater than one. The search dives towards the goal, expanding far fewer
+//! candidates, and the result costs at most the weight times the optimum.
+
+use std::cmp::{Eq, Ord, Ordering, PartialEq, PartialOrd};
+use std::collections::BinaryHeap;
+use std::default::Default;
+
+use super::cache::BasicCache;
+use super::{ApproximateSearch, SearchAlgorithm, SearchQueue};
+use crate::errors::Result;
+use crate::traits::{SearchCacher, SearchCandidate, SearchEstimate};
+use crate::Approximate;
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Weighted<S>
+where
+    S: SearchEstimate,
+{
+    key: S::Cost,
+    candidate: S,
+}
+
+impl<S> PartialEq for Weighted<S>
+where
+    S: SearchEstimate,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.key.eq(&other.key)
+    }
+}
+
+impl<S> Eq for Weighted<S> where S: SearchEstimate {}
+
+impl<S> Ord for Weighted<S>
+where
+    S: SearchEstimate,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key).reverse()
+    }
+}
+
+impl<S> PartialOrd for Weighted<S>
+where
+    S: SearchEstimate,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A priority queue ordered by the cost so far plus the weighted
+/// estimate of the cost remaining, from [SearchEstimate].
+#[derive(Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "S::Cost: serde::Serialize, Weighted<S>: serde::Serialize",
+        deserialize = "S::Cost: serde::Deserialize<'de>, Weighted<S>: serde::Deserialize<'de>"
+    ))
+)]
+pub struct WeightedQueue<S>
+where
+    S: SearchEstimate,
+{
+    // Numerator and denominator, or None for an unweighted A* search.
+    weight: Option<(S::Cost, S::Cost)>,
+    queue: BinaryHeap<Weighted<S>>,
+}
+
+impl<S> WeightedQueue<S>
+where
+    S: SearchEstimate,
+{
+    /// Weight estimates by `numerator / denominator`.
+    ///
+    /// # Panics
+    ///
+    /// If the denominator is zero.
+    pub fn new(numerator: S::Cost, denominator: S::Cost) -> Self {
+        // Costs have no zero to compare against, but only zero is
+        // unchanged when added to itself.
+        assert!(
+            denominator + denominator != denominator,
+            "Weight denominator must not be zero"
+        );
+        WeightedQueue {
+            weight: Some((numerator, denominator)),
+            queue: BinaryHeap::new(),
+        }
+    }
+
+    // Scaled through by the denominator, so that costs don't need
+    // to support division.
+    fn key(&self, candidate: &S) -> S::Cost {
+        match self.weight {
+            Some((n, d)) => candidate.cost() * d + candidate.remaining() * n,
+            None => candidate.cost() + candidate.remaining(),
+        }
+    }
+}
+
+impl<S> Default for WeightedQueue<S>
+where
+    S: SearchEstimate,
+{
+    fn default() -> Self {
+        WeightedQueue {
+            weight: None,
+            queue: BinaryHeap::new(),
+        }
+    }
+}
+
+impl<S> SearchQueue for WeightedQueue<S>
+where
+    S: SearchEstimate,
+{
+    type Candidate = S;
+
+    fn pop(&mut self) -> Option<Self::Candidate> {
+        self.queue.pop().map(|w| w.candidate)
+    }
+
+    fn push(&mut self, item: Self::Candidate) {
+        self.queue.push(Weighted {
+            key: self.key(&item),
+            candidate: item,
+        });
+    }
+
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn can_terminate(&self, candidate: &Self::Candidate) -> bool {
+        let best = self.key(candidate);
+        self.queue.peek().map(|w| w.key >= best).unwrap_or(true)
+    }
+
+    fn can_terminate_ties(&self, candidate: &Self::Candidate) -> bool {
+        let best = self.key(candidate);
+        self.queue.peek().map(|w| w.key > best).unwrap_or(true)
+    }
+}
+
+type WeightedSearcher<S> = ApproximateSearch<S, WeightedQueue<S>, BasicCache<S>>;
+
+pub fn build<S>(origin: S, numerator: S::Cost, denominator: S::Cost) -> WeightedSearcher<S>
+where
+    S: SearchEstimate + SearchCandidate + SearchCacher + Ord,
+{
+    // A weight of at most one never overestimates, so remains optimal.
+    let optimal = numerator <= denominator;
+    ApproximateSearch::new(
+        SearchAlgorithm::new_with_queue(origin, WeightedQueue::new(numerator, denominator)),
+        optimal,
+    )
+}
+
+/// Perform a weighted A* search, inflating estimates by
+/// `numerator / denominator`.
+///
+/// The result is reported as optimal only when the weight is at most one.
+pub fn run<S>(origin: S, numerator: S::Cost, denominator: S::Cost) -> Result<Approximate<S>>
+where
+    S: SearchEstimate + SearchCandidate + SearchCacher + Ord,
+{
+    build(origin, numerator, denominator).run()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::GridWalker;
+
+    #[test]
+    fn weighted() {
+        let found = run(GridWalker::new(8), 3, 1).unwrap();
+        assert!(!found.optimal);
+        assert_eq!(found.candidate.position, (8, 8));
+        assert!(found.candidate.steps <= 3 * 16);
+
+        let found = run(GridWalker::new(8), 1, 1).unwrap();
+        assert!(found.optimal);
+        assert_eq!(found.candidate.steps, 16);
+    }
+
+    #[test]
+    #[should_panic(expected = "denominator")]
+    fn zero_denominator() {
+        let _ = run(GridWalker::new(8), 1, 0);
+    }
+}
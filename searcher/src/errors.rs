@@ -12,6 +12,9 @@ pub enum SearchError {
 
     #[error("Time limit exhausted after {0:?}")]
     TimeLimitExhausted(time::Duration),
+
+    #[error("Search was cancelled")]
+    Cancelled,
 }
 
 /// Result when a search method might fail.
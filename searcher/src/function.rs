@@ -0,0 +1,414 @@
+//! Searches described by closures, rather than by implementing the
+//! search traits on a dedicated candidate type.
+//!
+//! ```
+//! // Count up from 1 to 10 by adding one or doubling.
+//! let solution = searcher::from_fn(1, |n: &u32| vec![(n + 1, 1), (n * 2, 1)], |n| *n == 10)
+//!     .bfs()
+//!     .unwrap();
+//!
+//! assert_eq!(solution.path, vec![1, 2, 4, 5, 10]);
+//! assert_eq!(solution.cost, 4);
+//! ```
+
+use std::cmp::{Eq, Ord, Ordering, PartialEq, PartialOrd};
+use std::fmt;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::ops::Add;
+use std::rc::Rc;
+
+use crate::algorithm::{astar, dijkstra, SearchOptions};
+use crate::errors::Result;
+use crate::traits::{SearchCacher, SearchCandidate, SearchHeuristic, SearchState};
+
+/// The path found by a search, from the start state to a goal,
+/// along with the total cost of the steps taken.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution<S, C> {
+    pub path: Vec<S>,
+    pub cost: C,
+}
+
+/// A search over states produced by closures. Build one with [from_fn].
+pub struct FnSearch<S, C, F, G, H = fn(&S) -> C> {
+    start: S,
+    successors: F,
+    goal: G,
+    heuristic: Option<H>,
+    options: SearchOptions,
+    cost: PhantomData<C>,
+}
+
+/// Search from a start state, where `successors` gives the states reachable
+/// in one step along with the cost of each step, and `goal` identifies the
+/// states which end the search.
+pub fn from_fn<S, C, F, G, I>(start: S, successors: F, goal: G) -> FnSearch<S, C, F, G>
+where
+    F: Fn(&S) -> I,
+    I: IntoIterator<Item = (S, C)>,
+    G: Fn(&S) -> bool,
+{
+    FnSearch {
+        start,
+        successors,
+        goal,
+        heuristic: None,
+        options: SearchOptions::default(),
+        cost: PhantomData,
+    }
+}
+
+impl<S, C, F, G, H> Debug for FnSearch<S, C, F, G, H>
+where
+    S: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FnSearch")
+            .field("start", &self.start)
+            .field("heuristic", &self.heuristic.is_some())
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+impl<S, C, F, G, H, I> FnSearch<S, C, F, G, H>
+where
+    S: Debug + Clone + Eq + Hash,
+    C: Debug + Copy + Ord + Default + Add<Output = C>,
+    F: Fn(&S) -> I,
+    I: IntoIterator<Item = (S, C)>,
+    G: Fn(&S) -> bool,
+    H: Fn(&S) -> C,
+{
+    /// Estimate the remaining cost from a state to the nearest goal, used
+    /// by [FnSearch::astar]. The estimate must never be more than the real
+    /// cost for the path found to be the cheapest.
+    pub fn with_heuristic<H2>(self, heuristic: H2) -> FnSearch<S, C, F, G, H2>
+    where
+        H2: Fn(&S) -> C,
+    {
+        FnSearch {
+            start: self.start,
+            successors: self.successors,
+            goal: self.goal,
+            heuristic: Some(heuristic),
+            options: self.options,
+            cost: self.cost,
+        }
+    }
+
+    pub fn with_options(mut self, options: SearchOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Find the path with the fewest steps, ignoring their costs.
+    pub fn bfs(mut self) -> Result<Solution<S, C>> {
+        // Ranking candidates by their number of steps turns the priority
+        // queue used for Dijkstra's algorithm into a breadth-first queue.
+        let options = std::mem::take(&mut self.options);
+        let origin = FnCandidate::start(&self, Order::Steps);
+        Ok(dijkstra::build(origin)
+            .with_options(options)
+            .run()?
+            .solution())
+    }
+
+    /// Find the cheapest path using Dijkstra's algorithm.
+    pub fn dijkstra(mut self) -> Result<Solution<S, C>> {
+        let options = std::mem::take(&mut self.options);
+        let origin = FnCandidate::start(&self, Order::Cost);
+        Ok(dijkstra::build(origin)
+            .with_options(options)
+            .run()?
+            .solution())
+    }
+
+    /// Find the cheapest path using A*, guided by the heuristic. Without
+    /// a heuristic this is the same as [FnSearch::dijkstra].
+    pub fn astar(mut self) -> Result<Solution<S, C>> {
+        let options = std::mem::take(&mut self.options);
+        let origin = FnCandidate::start(&self, Order::Cost);
+        Ok(astar::build(origin).with_options(options).run()?.solution())
+    }
+}
+
+// Object safe view of the closures, so that candidates don't need
+// to carry every closure type as a parameter.
+trait Problem<S, C> {
+    fn start(&self) -> &S;
+    fn successors(&self, state: &S) -> Vec<(S, C)>;
+    fn is_goal(&self, state: &S) -> bool;
+    fn heuristic(&self, state: &S) -> C;
+}
+
+impl<S, C, F, G, H, I> Problem<S, C> for FnSearch<S, C, F, G, H>
+where
+    C: Default,
+    F: Fn(&S) -> I,
+    I: IntoIterator<Item = (S, C)>,
+    G: Fn(&S) -> bool,
+    H: Fn(&S) -> C,
+{
+    fn start(&self) -> &S {
+        &self.start
+    }
+
+    fn successors(&self, state: &S) -> Vec<(S, C)> {
+        (self.successors)(state).into_iter().collect()
+    }
+
+    fn is_goal(&self, state: &S) -> bool {
+        (self.goal)(state)
+    }
+
+    fn heuristic(&self, state: &S) -> C {
+        self.heuristic
+            .as_ref()
+            .map(|h| h(state))
+            .unwrap_or_default()
+    }
+}
+
+/// How candidates are ranked against each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Order {
+    Steps,
+    Cost,
+}
+
+// A state along with the way it was reached. Paths share their
+// common prefix, so that taking a step doesn't copy the whole path.
+#[derive(Debug)]
+struct Step<S> {
+    state: S,
+    previous: Option<Rc<Step<S>>>,
+}
+
+#[derive(Clone)]
+struct FnCandidate<'f, S, C> {
+    step: Rc<Step<S>>,
+    cost: C,
+    steps: usize,
+    order: Order,
+    problem: &'f dyn Problem<S, C>,
+}
+
+impl<'f, S, C> Debug for FnCandidate<'f, S, C>
+where
+    S: Debug,
+    C: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FnCandidate")
+            .field("state", &self.step.state)
+            .field("cost", &self.cost)
+            .field("steps", &self.steps)
+            .finish()
+    }
+}
+
+impl<'f, S, C> FnCandidate<'f, S, C>
+where
+    S: Clone,
+    C: Copy + Ord + Default + Add<Output = C>,
+{
+    fn start(problem: &'f dyn Problem<S, C>, order: Order) -> Self {
+        Self {
+            step: Rc::new(Step {
+                state: problem.start().clone(),
+                previous: None,
+            }),
+            cost: C::default(),
+            steps: 0,
+            order,
+            problem,
+        }
+    }
+
+    // Candidates are ranked by cost, with fewer steps breaking ties.
+    // Breadth-first searches rank every cost as equal.
+    fn rank(&self) -> (C, usize) {
+        match self.order {
+            Order::Steps => (C::default(), self.steps),
+            Order::Cost => (self.cost, self.steps),
+        }
+    }
+
+    fn solution(self) -> Solution<S, C> {
+        let mut path = Vec::with_capacity(self.steps + 1);
+        let mut step = Some(&self.step);
+        while let Some(s) = step {
+            path.push(s.state.clone());
+            step = s.previous.as_ref();
+        }
+        path.reverse();
+
+        Solution {
+            path,
+            cost: self.cost,
+        }
+    }
+}
+
+impl<'f, S, C> SearchCandidate for FnCandidate<'f, S, C>
+where
+    S: Debug + Clone,
+    C: Debug + Copy + Ord + Default + Add<Output = C>,
+{
+    fn is_complete(&self) -> bool {
+        self.problem.is_goal(&self.step.state)
+    }
+
+    fn children(&self) -> Vec<Self> {
+        self.problem
+            .successors(&self.step.state)
+            .into_iter()
+            .map(|(state, cost)| Self {
+                step: Rc::new(Step {
+                    state,
+                    previous: Some(self.step.clone()),
+                }),
+                cost: self.cost + cost,
+                steps: self.steps + 1,
+                order: self.order,
+                problem: self.problem,
+            })
+            .collect()
+    }
+}
+
+impl<'f, S, C> SearchState for FnCandidate<'f, S, C>
+where
+    S: Debug + Clone + Eq + Hash,
+    C: Debug + Copy + Ord + Default + Add<Output = C>,
+{
+    type State = S;
+
+    fn state(&self) -> Self::State {
+        self.step.state.clone()
+    }
+}
+
+impl<'f, S, C> SearchCacher for FnCandidate<'f, S, C>
+where
+    S: Debug + Clone + Eq + Hash,
+    C: Debug + Copy + Ord + Default + Add<Output = C>,
+{
+    type Value = (C, usize);
+
+    fn value(&self) -> Self::Value {
+        self.rank()
+    }
+}
+
+impl<'f, S, C> SearchHeuristic for FnCandidate<'f, S, C>
+where
+    S: Debug + Clone,
+    C: Debug + Copy + Ord + Default + Add<Output = C>,
+{
+    type Hueristic = (C, usize);
+
+    fn heuristic(&self) -> Self::Hueristic {
+        let (cost, steps) = self.rank();
+        (cost + self.problem.heuristic(&self.step.state), steps)
+    }
+}
+
+impl<'f, S, C> Ord for FnCandidate<'f, S, C>
+where
+    C: Copy + Ord + Default + Add<Output = C>,
+    S: Clone,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank().cmp(&other.rank()).reverse()
+    }
+}
+
+impl<'f, S, C> PartialOrd for FnCandidate<'f, S, C>
+where
+    C: Copy + Ord + Default + Add<Output = C>,
+    S: Clone,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'f, S, C> PartialEq for FnCandidate<'f, S, C>
+where
+    C: Copy + Ord + Default + Add<Output = C>,
+    S: Clone,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.rank().eq(&other.rank())
+    }
+}
+
+impl<'f, S, C> Eq for FnCandidate<'f, S, C>
+where
+    C: Copy + Ord + Default + Add<Output = C>,
+    S: Clone,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A small weighted graph where the direct edge to the goal is
+    // more expensive than going the long way round.
+    fn edges(node: &char) -> Vec<(char, u32)> {
+        match node {
+            'a' => vec![('b', 1), ('e', 10)],
+            'b' => vec![('c', 1), ('a', 1)],
+            'c' => vec![('d', 1)],
+            'd' => vec![('e', 1)],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn bfs_fewest_steps() {
+        let solution = from_fn('a', edges, |n| *n == 'e').bfs().unwrap();
+        assert_eq!(solution.path, vec!['a', 'e']);
+        assert_eq!(solution.cost, 10);
+    }
+
+    #[test]
+    fn dijkstra_cheapest() {
+        let solution = from_fn('a', edges, |n| *n == 'e').dijkstra().unwrap();
+        assert_eq!(solution.path, vec!['a', 'b', 'c', 'd', 'e']);
+        assert_eq!(solution.cost, 4);
+    }
+
+    #[test]
+    fn astar_grid() {
+        // Walk around a wall on an open grid.
+        let wall = |(x, y): (i32, i32)| x == 2 && y < 4;
+        let successors = |&(x, y): &(i32, i32)| {
+            vec![(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
+                .into_iter()
+                .filter(|&(x, y)| (0..6).contains(&x) && (0..6).contains(&y))
+                .filter(move |&p| !wall(p))
+                .map(|p| (p, 1))
+                .collect::<Vec<_>>()
+        };
+
+        let solution = from_fn((0, 0), successors, |p| *p == (4, 0))
+            .with_heuristic(|&(x, y)| (4 - x).abs() + y.abs())
+            .astar()
+            .unwrap();
+        assert_eq!(solution.cost, 12);
+        assert_eq!(solution.path.first(), Some(&(0, 0)));
+        assert_eq!(solution.path.last(), Some(&(4, 0)));
+    }
+
+    #[test]
+    fn unreachable() {
+        let result = from_fn('a', edges, |n| *n == 'z').dijkstra();
+        assert!(matches!(result, Err(crate::SearchError::NoResultFound)));
+    }
+}
@@ -183,8 +183,7 @@ where
     /// Returns None when no path can be found, or when origin or destination
     /// are not nodes in the graph.
     pub fn find_path(&self, origin: N, destination: N) -> Option<GraphPath<N, E>> {
-        use crate::dijkstra;
-        use crate::SearchOptions;
+        use crate::bidirectional;
 
         // Chech that start and endpoints are nodes.
         // TODO: Could dynamically add nodes to the graph as new options appear?
@@ -192,39 +191,31 @@ where
             return None;
         }
 
-        let options = {
-            let mut o = SearchOptions::default();
-            o.verbose = Some(1);
-            o.exhaustive = true;
-            o
-        };
-
-        let c = graphsearch::GraphPathCandidate::start(origin, &destination, &self);
-        dijkstra::build(c)
-            .with_options(options)
-            .run()
-            .ok()
-            .map(|c| c.path)
+        // Edges are stored in both directions, so the same candidate
+        // can search backwards from the destination.
+        let forward = graphsearch::GraphPathCandidate::start(origin.clone(), &destination, self);
+        let backward = graphsearch::GraphPathCandidate::start(destination.clone(), &origin, self);
+        bidirectional::run(forward, backward).ok().map(|c| c.path)
     }
 }
 
 mod graphsearch {
 
-    use std::cmp::{Eq, Ord, Ordering, PartialEq, PartialOrd};
     use std::fmt::Debug;
     use std::hash::Hash;
     use std::iter::Sum;
 
     use super::path::GraphPath;
     use super::{Edge, Graph};
-    use crate::{SearchCacher, SearchCandidate, SearchState};
+    use crate::{SearchCacher, SearchCandidate, SearchOrd, SearchReversible, SearchState};
 
-    #[derive(Debug, Clone)]
+    #[derive(Debug, Clone, SearchOrd)]
     pub(crate) struct GraphPathCandidate<'m, N, E>
     where
         N: Debug + Clone,
         E: Edge<Node = N>,
     {
+        #[search(cost)]
         pub(crate) path: GraphPath<N, E>,
         destination: &'m N,
         graph: &'m Graph<E>,
@@ -303,40 +294,22 @@ mod graphsearch {
         }
     }
 
-    impl<'m, N, E> Ord for GraphPathCandidate<'m, N, E>
+    impl<'m, N, E> SearchReversible for GraphPathCandidate<'m, N, E>
     where
         N: Debug + Clone + Hash + Eq + PartialEq,
         E: Edge<Weight = usize, Node = N>,
     {
-        fn cmp(&self, other: &Self) -> Ordering {
-            self.path.weight().cmp(&other.path.weight()).reverse()
-        }
-    }
-
-    impl<'m, N, E> PartialOrd for GraphPathCandidate<'m, N, E>
-    where
-        N: Debug + Clone + Hash + Eq + PartialEq,
-        E: Edge<Weight = usize, Node = N>,
-    {
-        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-            Some(self.cmp(other))
-        }
-    }
-
-    impl<'m, N, E> PartialEq for GraphPathCandidate<'m, N, E>
-    where
-        N: Debug + Clone + Hash + Eq + PartialEq,
-        E: Edge<Weight = usize, Node = N>,
-    {
-        fn eq(&self, other: &Self) -> bool {
-            self.path.weight().eq(&other.path.weight())
+        fn join(&self, backward: &Self) -> Self {
+            let mut path = self.path.clone();
+            path.nodes
+                .extend(backward.path.nodes.iter().rev().skip(1).cloned());
+            path.edges
+                .extend(backward.path.edges.iter().rev().map(|e| e.reverse()));
+            Self {
+                path,
+                destination: self.destination,
+                graph: self.graph,
+            }
         }
     }
-
-    impl<'m, N, E> Eq for GraphPathCandidate<'m, N, E>
-    where
-        N: Debug + Clone + Hash + Eq + PartialEq,
-        E: Edge<Weight = usize, Node = N>,
-    {
-    }
 }
@@ -1,24 +1,236 @@
 //! Generalized search algorithms, especially useful for graph traversal.
 //!
 //! To use these search algorithms, implement at least the trait [SearchCandidate]
-//! which must provide a few methods
+//! which must provide a few methods. The ordering, [SearchState] and
+//! [SearchCacher] impls can be derived with `#[derive(SearchOrd)]`,
+//! `#[derive(SearchState)]` and `#[derive(SearchCacher)]`, by marking
+//! fields with `#[search(cost)]` and `#[search(state)]`.
+
+// Lets derived impls, which name `::searcher`, be used within this crate.
+extern crate self as searcher;
 
 pub mod algorithm;
 mod errors;
+mod function;
 pub mod graph;
+#[cfg(test)]
+mod testing;
 mod traits;
 
 pub use algorithm::score::Score;
 pub use errors::Result as SearchResult;
 pub use errors::SearchError;
+pub use function::{from_fn, FnSearch, Solution};
+pub use searcher_derive::{SearchCacher, SearchOrd, SearchState};
 pub use traits::SearchCacher;
 pub use traits::SearchCandidate;
+pub use traits::SearchEstimate;
 pub use traits::SearchHeuristic;
+pub use traits::SearchReversible;
 pub use traits::SearchScore;
 pub use traits::SearchState;
 
 pub use algorithm::astar;
 pub use algorithm::basic::bfs;
 pub use algorithm::basic::dfs;
+pub use algorithm::beam;
+pub use algorithm::bidirectional;
+pub use algorithm::checkpoint::Checkpoint;
 pub use algorithm::dijkstra;
+pub use algorithm::iterative::idastar;
+pub use algorithm::iterative::iddfs;
+pub use algorithm::observer::{CancelToken, SearchEvent, SearchObserver};
+pub use algorithm::parallel;
+pub use algorithm::sma;
+pub use algorithm::weighted;
+pub use algorithm::Anytime;
+pub use algorithm::Approximate;
 pub use algorithm::SearchOptions;
+pub use algorithm::SearchStats;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::LineWalker;
+
+    // Fetches an item at -2 before walking to 3, so that its state
+    // is made up of two fields.
+    #[derive(Debug, Clone, SearchOrd, SearchState, SearchCacher)]
+    struct Walker {
+        #[search(state)]
+        position: i32,
+        #[search(state)]
+        carrying: bool,
+        #[search(cost)]
+        steps: usize,
+    }
+
+    impl SearchCandidate for Walker {
+        fn is_complete(&self) -> bool {
+            self.position == 3 && self.carrying
+        }
+
+        fn children(&self) -> Vec<Self> {
+            let step = |position, carrying| Walker {
+                position,
+                carrying: carrying || position == -2,
+                steps: self.steps + 1,
+            };
+            vec![
+                step(self.position + 1, self.carrying),
+                step(self.position - 1, self.carrying),
+            ]
+        }
+    }
+
+    #[test]
+    fn derived_candidate() {
+        let walker = |steps| Walker {
+            position: 0,
+            carrying: false,
+            steps,
+        };
+        assert!(walker(1) > walker(2));
+        assert_eq!(walker(1), walker(1));
+        assert_eq!(walker(4).state(), (0, false));
+        assert_eq!(walker(4).value(), 4);
+
+        // Fetch the item at -2 before walking to 3.
+        let found = dijkstra::run(walker(0)).unwrap();
+        assert_eq!(found.steps, 7);
+    }
+
+    #[test]
+    fn search_stats() {
+        let (found, stats) = dijkstra::build(LineWalker::new(7))
+            .run_with_stats()
+            .unwrap();
+        assert_eq!(found.cost, 7);
+
+        // Every expanded walker has three children, and the origin is
+        // generated without a parent.
+        assert_eq!(stats.generated, 3 * stats.expanded + 1);
+        assert!(stats.complete >= 1);
+        assert!(stats.cache_hits > 0);
+        assert!(stats.cache_hits + stats.cache_misses < stats.generated);
+        assert!(stats.peak_queue > 0);
+    }
+
+    #[test]
+    fn observer() {
+        use std::sync::{Arc, Mutex};
+
+        let counts = Arc::new(Mutex::new([0; 3]));
+        let observed = counts.clone();
+        let (_, stats) = dijkstra::build(LineWalker::new(7))
+            .with_observer(move |event: SearchEvent<'_, LineWalker>| {
+                let i = match event {
+                    SearchEvent::Expanded(_) => 0,
+                    SearchEvent::Pushed(_) => 1,
+                    SearchEvent::Completed(_) => 2,
+                };
+                observed.lock().unwrap()[i] += 1;
+            })
+            .run_with_stats()
+            .unwrap();
+
+        let counts = counts.lock().unwrap();
+        assert_eq!(counts[0], stats.expanded);
+        assert_eq!(counts[1], stats.cache_misses);
+        assert_eq!(counts[2], stats.complete);
+    }
+
+    #[test]
+    fn cancellation() {
+        // Run the search on a worker thread, and cancel it from within
+        // the search, as the thread watching it would.
+        let token = CancelToken::new();
+        let canceller = token.clone();
+        let search = dijkstra::build(LineWalker::new(7))
+            .with_cancellation(token)
+            .with_observer(move |event: SearchEvent<'_, LineWalker>| {
+                if let SearchEvent::Expanded(w) = event {
+                    if w.cost >= 3 {
+                        canceller.cancel();
+                    }
+                }
+            });
+        let result = std::thread::spawn(move || search.run()).join().unwrap();
+        assert!(matches!(result, Err(SearchError::Cancelled)));
+    }
+
+    // Picks a cost for each of a fixed number of steps.
+    #[derive(Debug, Clone, SearchOrd)]
+    struct Picker {
+        picked: usize,
+        #[search(cost)]
+        cost: usize,
+    }
+
+    impl SearchCandidate for Picker {
+        fn is_complete(&self) -> bool {
+            self.picked == 4
+        }
+
+        fn children(&self) -> Vec<Self> {
+            // Depth-first search tries the last, most expensive, child first.
+            (1..=3)
+                .map(|c| Picker {
+                    picked: self.picked + 1,
+                    cost: self.cost + c,
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn anytime() {
+        let origin = Picker { picked: 0, cost: 0 };
+        let costs: Vec<_> = dfs::build(origin)
+            .run_anytime()
+            .map(|r| r.unwrap().cost)
+            .collect();
+        assert!(costs.len() > 1);
+        assert_eq!(costs.last(), Some(&4));
+        assert!(costs.windows(2).all(|w| w[0] > w[1]));
+
+        let options = SearchOptions {
+            limit: Some(20),
+            ..SearchOptions::default()
+        };
+        let origin = Picker { picked: 0, cost: 0 };
+        let results: Vec<_> = dfs::build(origin)
+            .with_options(options)
+            .run_anytime()
+            .collect();
+        assert!(results[0].is_ok());
+        assert!(matches!(
+            results.last(),
+            Some(Err(SearchError::StepLimitExhausted(20)))
+        ));
+    }
+
+    // A tuple struct, ranked by the length of its route.
+    #[derive(Debug, Clone, SearchOrd, SearchState, SearchCacher)]
+    struct Route(
+        #[search(state)] char,
+        #[search(cost = "len", value = "usize")] Vec<char>,
+    );
+
+    impl SearchCandidate for Route {
+        fn is_complete(&self) -> bool {
+            true
+        }
+
+        fn children(&self) -> Vec<Self> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn cost_method() {
+        assert!(Route('a', vec!['b']) > Route('a', vec!['b', 'c']));
+        assert_eq!(Route('a', vec!['b', 'c']).state(), 'a');
+        assert_eq!(Route('a', vec!['b', 'c']).value(), 2);
+    }
+}
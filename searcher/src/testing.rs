@@ -0,0 +1,120 @@
+//! Walkers shared by the tests of the search algorithms.
+
+use crate::{
+    SearchCacher, SearchCandidate, SearchEstimate, SearchHeuristic, SearchOrd, SearchState,
+};
+
+/// Walks along a line towards a target, one step either way, or
+/// three at once for a cost of four.
+#[derive(Debug, Clone, SearchOrd, SearchState, SearchCacher)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct LineWalker {
+    #[search(state)]
+    pub position: i32,
+    #[search(cost)]
+    pub cost: i32,
+    pub target: i32,
+}
+
+impl LineWalker {
+    pub fn new(target: i32) -> Self {
+        LineWalker {
+            position: 0,
+            cost: 0,
+            target,
+        }
+    }
+}
+
+impl SearchCandidate for LineWalker {
+    fn is_complete(&self) -> bool {
+        self.position == self.target
+    }
+
+    fn children(&self) -> Vec<Self> {
+        // Long strides cost more than three short steps.
+        [(1, 1), (-1, 1), (3, 4)]
+            .iter()
+            .map(|(d, c)| LineWalker {
+                position: self.position + d,
+                cost: self.cost + c,
+                target: self.target,
+            })
+            .collect()
+    }
+}
+
+impl SearchHeuristic for LineWalker {
+    type Hueristic = i32;
+
+    fn heuristic(&self) -> i32 {
+        self.cost + (self.target - self.position).abs()
+    }
+}
+
+/// Walks across a square grid from (0, 0) to (size, size),
+/// remembering the route taken.
+#[derive(Debug, Clone, SearchOrd, SearchState, SearchCacher)]
+pub(crate) struct GridWalker {
+    #[search(state)]
+    pub position: (i32, i32),
+    #[search(cost)]
+    pub steps: usize,
+    pub size: i32,
+    pub route: Vec<(i32, i32)>,
+}
+
+impl GridWalker {
+    pub fn new(size: i32) -> Self {
+        GridWalker {
+            position: (0, 0),
+            steps: 0,
+            size,
+            route: vec![(0, 0)],
+        }
+    }
+}
+
+impl SearchCandidate for GridWalker {
+    fn is_complete(&self) -> bool {
+        self.position == (self.size, self.size)
+    }
+
+    fn children(&self) -> Vec<Self> {
+        let (x, y) = self.position;
+        [(x + 1, y), (x, y + 1), (x - 1, y), (x, y - 1)]
+            .iter()
+            .filter(|(x, y)| (0..=self.size).contains(x) && (0..=self.size).contains(y))
+            .map(|&position| {
+                let mut route = self.route.clone();
+                route.push(position);
+                GridWalker {
+                    position,
+                    steps: self.steps + 1,
+                    size: self.size,
+                    route,
+                }
+            })
+            .collect()
+    }
+}
+
+impl SearchEstimate for GridWalker {
+    type Cost = usize;
+
+    fn cost(&self) -> usize {
+        self.steps
+    }
+
+    fn remaining(&self) -> usize {
+        (2 * self.size - self.position.0 - self.position.1) as usize
+    }
+}
+
+impl SearchHeuristic for GridWalker {
+    type Hueristic = usize;
+
+    fn heuristic(&self) -> usize {
+        self.cost() + self.remaining()
+    }
+}
@@ -1,5 +1,6 @@
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::ops::{Add, Mul};
 
 /// Provides an interface for conducting searches.
 ///
@@ -39,6 +40,19 @@ pub trait SearchHeuristic: SearchCandidate {
     fn heuristic(&self) -> Self::Hueristic;
 }
 
+/// An interface for heuristics which are split into the cost so far and
+/// the estimated cost remaining, so that the estimate can be weighted.
+pub trait SearchEstimate: SearchCandidate {
+    type Cost: Debug + Copy + Ord + Add<Output = Self::Cost> + Mul<Output = Self::Cost>;
+
+    /// Cost of the candidate so far.
+    fn cost(&self) -> Self::Cost;
+
+    /// Best guess of the cost still to come, which should never
+    /// overestimate for the result to be within the weight of optimal.
+    fn remaining(&self) -> Self::Cost;
+}
+
 pub trait SearchState: SearchCandidate + Clone {
     type State: Debug + Eq + Hash;
     fn state(&self) -> Self::State;
@@ -57,3 +71,15 @@ pub trait SearchCacher: SearchState {
 
     fn value(&self) -> Self::Value;
 }
+
+/// An interface for searching from both ends at once.
+///
+/// The backward search starts from a candidate at the goal, and explores
+/// the same edges in reverse. Once both searches reach the same state,
+/// their candidates are joined into one which runs from the origin to
+/// the goal, with a value that is the sum of the two.
+pub trait SearchReversible: SearchCacher {
+    /// Join this candidate with one from the backward search which
+    /// ends in the same state.
+    fn join(&self, backward: &Self) -> Self;
+}
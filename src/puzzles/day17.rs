@@ -1,5 +1,5 @@
 use anyhow::{anyhow, Error};
-use intcode::{CPUState, Computer, Program};
+use intcode::{CPUState, Computer, Patch, Program};
 
 use std::convert::TryInto;
 use std::io::Read;
@@ -24,7 +24,8 @@ impl Camera {
     }
 
     fn new_activated(mut program: Program) -> Self {
-        program.insert(0, 2).unwrap();
+        // Wake up the vacuum robot, so it will accept movement routines.
+        program.apply_patch(&Patch::new().replace(0, 1, 2)).unwrap();
         Camera::new(program)
     }
 
@@ -48,19 +48,21 @@ pub(crate) fn main(input: Box<dyn Read + 'static>) -> ::std::result::Result<(),
         let start = time::Instant::now();
         let mm = map::MultiMap::new(map.clone());
 
-        let sp = multi::search(&mm)?;
+        let (sp, stats) = multi::search_with_stats(&mm)?;
         println!("Part 2: {}", sp.distance());
         println!("  Keys: {}", sp.keys().to_string());
         println!("  Time: {}s", start.elapsed().as_secs());
+        println!("  Search: {}", stats);
     }
 
     {
         let start = time::Instant::now();
 
-        let sp = single::search(&map)?;
+        let (sp, stats) = single::search_with_stats(&map)?;
         println!("Part 1: {}", sp.distance());
         println!("  Keys: {}", sp.keys().to_string());
         println!("  Time: {}s", start.elapsed().as_secs());
+        println!("  Search: {}", stats);
     }
 
     Ok(())
@@ -82,7 +84,7 @@ mod test {
             .parse()
             .unwrap();
 
-        let sp = single::search(&map).unwrap();
+        let sp = single::search_with_stats(&map).unwrap().0;
         assert_eq!(sp.distance(), 8);
         assert_eq!(sp.keys().to_string(), "a,b");
     }
@@ -99,7 +101,7 @@ mod test {
         .parse()
         .unwrap();
 
-        let sp = single::search(&map).unwrap();
+        let sp = single::search_with_stats(&map).unwrap().0;
         assert_eq!(sp.distance(), 86);
     }
 
@@ -115,7 +117,7 @@ mod test {
         .parse()
         .unwrap();
 
-        let sp = single::search(&map).unwrap();
+        let sp = single::search_with_stats(&map).unwrap().0;
         assert_eq!(sp.distance(), 132);
         assert_eq!(sp.keys().to_string(), "b,a,c,d,f,e,g")
     }
@@ -138,7 +140,7 @@ mod test {
 
         // assert!(search(&map).is_err());
 
-        let sp = single::search(&map).unwrap();
+        let sp = single::search_with_stats(&map).unwrap().0;
         assert_eq!(sp.distance(), 136);
     }
 
@@ -154,7 +156,7 @@ mod test {
         .parse()
         .unwrap();
 
-        let sp = single::search(&map).unwrap();
+        let sp = single::search_with_stats(&map).unwrap().0;
         assert_eq!(sp.distance(), 62);
         assert_eq!(sp.keys().to_string(), "a,b,j,g,n,f,o,k")
     }
@@ -172,7 +174,7 @@ mod test {
         .parse()
         .unwrap();
 
-        let sp = single::search(&map).unwrap();
+        let sp = single::search_with_stats(&map).unwrap().0;
         eprintln!("{}", sp.keys().to_string());
         assert_eq!(sp.distance(), 81);
     }
@@ -181,7 +183,7 @@ mod test {
     fn answer_part_1() {
         let input = get_default_input(18).unwrap();
         let map = read_map(input).unwrap();
-        assert_eq!(single::search(&map).unwrap().distance(), 5102);
+        assert_eq!(single::search_with_stats(&map).unwrap().0.distance(), 5102);
     }
 
     #[test]
@@ -203,7 +205,7 @@ mod test {
         }
 
         {
-            let mp = multi::search(&mmap).unwrap();
+            let mp = multi::search_with_stats(&mmap).unwrap().0;
             assert_eq!(mp.distance(), 8);
         }
     }
@@ -223,7 +225,7 @@ mod test {
         .unwrap();
 
         {
-            let mp = multi::search(&mmap).unwrap();
+            let mp = multi::search_with_stats(&mmap).unwrap().0;
             assert_eq!(mp.distance(), 24);
         }
     }
@@ -243,7 +245,7 @@ mod test {
         .unwrap();
 
         {
-            let mp = multi::search(&mmap).unwrap();
+            let mp = multi::search_with_stats(&mmap).unwrap().0;
             assert_eq!(mp.distance(), 32);
         }
     }
@@ -265,7 +267,7 @@ mod test {
         .unwrap();
 
         {
-            let mp = multi::search(&mmap).unwrap();
+            let mp = multi::search_with_stats(&mmap).unwrap().0;
             assert_eq!(mp.distance(), 72);
         }
     }
@@ -276,7 +278,7 @@ mod test {
         let map = read_map(input).unwrap();
         let mm = map::MultiMap::new(map.clone());
 
-        let sp = multi::search(&mm).unwrap();
+        let sp = multi::search_with_stats(&mm).unwrap().0;
         assert_eq!(sp.distance(), 2282);
     }
 }
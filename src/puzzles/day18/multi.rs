@@ -2,7 +2,7 @@ use anyhow::{anyhow, Error};
 use geometry::coord2d::graph;
 use geometry::coord2d::pathfinder;
 use geometry::coord2d::Point;
-use searcher::{self, Score, SearchCandidate, SearchScore, SearchState};
+use searcher::{self, Score, SearchCandidate, SearchScore, SearchState, SearchStats};
 
 use std::cmp::{Eq, PartialEq};
 
@@ -225,8 +225,9 @@ impl<'m> MultiSpelunker<'m> {
     }
 }
 
-#[allow(dead_code)]
-pub(crate) fn search<'m>(map: &'m map::MultiMap) -> Result<MultiSpelunkPath, Error> {
+pub(crate) fn search_with_stats<'m>(
+    map: &'m map::MultiMap,
+) -> Result<(MultiSpelunkPath, SearchStats), Error> {
     use geometry::coord2d::graph::Graphable;
     use searcher::SearchOptions;
 
@@ -252,9 +253,9 @@ pub(crate) fn search<'m>(map: &'m map::MultiMap) -> Result<MultiSpelunkPath, Err
             o
         };
 
-        Ok(searcher::dijkstra::build(origin)
+        Ok(searcher::parallel::dijkstra::build(origin)
             .with_options(options)
-            .run()
-            .map(|c| c.unwrap().path)?)
+            .run_with_stats()
+            .map(|(c, stats)| (c.unwrap().path, stats))?)
     }
 }
@@ -6,7 +6,9 @@ use anyhow::{anyhow, Error};
 use geometry::coord2d::graph;
 use geometry::coord2d::pathfinder;
 use geometry::coord2d::Point;
-use searcher::{self, Score, SearchCandidate, SearchHeuristic, SearchScore, SearchState};
+use searcher::{
+    self, Score, SearchCandidate, SearchHeuristic, SearchScore, SearchState, SearchStats,
+};
 
 use super::map::{self, TileMap};
 use super::KeyPath;
@@ -175,7 +177,9 @@ impl<'m> Spelunker<'m> {
     }
 }
 
-pub(crate) fn search<'m>(map: &'m map::Map) -> Result<SpelunkPath, Error> {
+pub(crate) fn search_with_stats<'m>(
+    map: &'m map::Map,
+) -> Result<(SpelunkPath, SearchStats), Error> {
     use geometry::coord2d::graph::Graphable;
     use searcher::SearchOptions;
 
@@ -188,8 +192,8 @@ pub(crate) fn search<'m>(map: &'m map::Map) -> Result<SpelunkPath, Error> {
         o
     };
 
-    Ok(searcher::dijkstra::build(origin)
+    Ok(searcher::parallel::dijkstra::build(origin)
         .with_options(options)
-        .run()
-        .map(|c| c.unwrap().path)?)
+        .run_with_stats()
+        .map(|(c, stats)| (c.unwrap().path, stats))?)
 }
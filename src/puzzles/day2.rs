@@ -1,5 +1,5 @@
 use anyhow::Error;
-use intcode::{Computer, IntMem};
+use intcode::{Computer, IntMem, Patch, Program};
 use std::io::{BufRead, BufReader, Read};
 
 pub(crate) fn main(input: Box<dyn Read + 'static>) -> ::std::result::Result<(), Error> {
@@ -17,9 +17,8 @@ pub(crate) fn main(input: Box<dyn Read + 'static>) -> ::std::result::Result<(),
         v
     };
 
-    let mut part1 = program.clone();
-    part1[1] = 12;
-    part1[2] = 2;
+    let mut part1: Program = program.clone().into();
+    part1.apply_patch(&Patch::new().set(1, 12).set(2, 2))?;
 
     let mut cpu = Computer::new(part1);
     cpu.run()?;
@@ -41,9 +40,10 @@ pub(crate) fn main(input: Box<dyn Read + 'static>) -> ::std::result::Result<(),
 }
 
 fn trial(program: &Vec<IntMem>, noun: IntMem, verb: IntMem) -> Option<IntMem> {
-    let mut part2 = program.clone();
-    part2[1] = noun;
-    part2[2] = verb;
+    let mut part2: Program = program.clone().into();
+    part2
+        .apply_patch(&Patch::new().set(1, noun).set(2, verb))
+        .ok()?;
 
     let mut cpu = Computer::new(part2);
     cpu.run().ok()?;